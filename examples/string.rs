@@ -41,7 +41,7 @@ where
     // 如果这个函数返回 None，map_opt 则会返回一个错误。
     // 在当前示例中，因为不是所有 u32 值是合法的 Unicode 码点，
     // 我们必须可失败的使用 from_u32 将其转换为 char。
-    map_opt(parse_u32, |value| std::char::from_u32(value))(input)
+    map_opt(parse_u32, std::char::from_u32)(input)
 }
 
 /// 解析一个转义字符：\n，\t，\r，\u{00AC}等。
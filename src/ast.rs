@@ -0,0 +1,520 @@
+//! AsciiDoc 文档树
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+
+use crate::diagnostic::Diagnostic;
+
+#[derive(Debug, PartialEq)]
+pub struct Document<'a> {
+    pub header: Option<Header<'a>>,
+    pub blocks: Vec<Block<'a>>,
+    pub attrs: Vec<Attribute<'a>>,
+    pub warnings: Vec<Diagnostic>,
+    /// 解析所用的原文，文档树中的文本都是它的切片
+    pub source: &'a str,
+    /// 可以被交叉引用的 id 和目标替换过属性引用的标题，没有标题的块为 `None`
+    pub references: HashMap<String, Option<String>>,
+}
+
+/// 源文件中的位置，行和列都从 1 开始，列按字符计数
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+/// 节点在源文件中的范围，`end` 指向最后一个字符之后
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Location {
+    pub start: Position,
+    pub end: Position,
+}
+
+impl Location {
+    /// 从 `start` 开始覆盖 `text` 的范围
+    pub fn covering(start: Position, text: &str) -> Self {
+        let mut end = start;
+        for c in text.chars() {
+            if c == '\n' {
+                end.line += 1;
+                end.column = 1;
+            } else {
+                end.column += 1;
+            }
+        }
+        Location { start, end }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Header<'a> {
+    pub title: &'a str,
+    pub auth_info: Vec<AuthorInfo<'a>>,
+    pub revision: Option<Revision<'a>>,
+    pub attrs: Vec<Attribute<'a>>,
+    pub location: Location,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Attribute<'a> {
+    pub unset: bool,
+    pub name: &'a str,
+    pub value: Option<&'a str>,
+    pub location: Location,
+}
+
+impl<'a> Attribute<'a> {
+    /// 按命令行 `-a` 的语法解析属性：`name=value`、`name`、`name!` 或 `!name`
+    pub fn from_override(i: &'a str) -> Self {
+        let (name, value) = match i.split_once('=') {
+            Some((name, value)) => (name, Some(value)),
+            None => (i, None),
+        };
+
+        let (unset, name, value) = match name.strip_prefix('!').or_else(|| name.strip_suffix('!')) {
+            Some(name) => (true, name, None),
+            None => (false, name, value),
+        };
+        Attribute {
+            unset,
+            name,
+            value,
+            location: Location::default(),
+        }
+    }
+}
+
+impl Header<'_> {
+    /// 由作者行派生的隐式属性，与 Asciidoctor 一致
+    ///
+    /// 每个作者都有带 `_n` 后缀的 `author`、`firstname`、`middlename`、`lastname`、
+    /// `authorinitials` 和 `email`，第一个作者同时设置不带后缀的版本。
+    pub fn author_attributes(&self) -> Vec<(String, String)> {
+        let mut attrs = vec![];
+        for (index, info) in self.auth_info.iter().enumerate() {
+            let name = &info.author;
+            let values = [
+                ("author", Some(name.fullname())),
+                ("firstname", Some(name.firstname.to_string())),
+                ("middlename", name.middle_name.map(str::to_string)),
+                ("lastname", name.lastname.map(str::to_string)),
+                ("authorinitials", Some(name.initials())),
+                ("email", info.email.map(str::to_string)),
+            ];
+
+            for (key, value) in values {
+                let Some(value) = value else { continue };
+                if index == 0 {
+                    attrs.push((key.to_string(), value.clone()));
+                }
+                attrs.push((format!("{key}_{}", index + 1), value));
+            }
+        }
+
+        if !self.auth_info.is_empty() {
+            let authors = self
+                .auth_info
+                .iter()
+                .map(|info| info.author.fullname())
+                .collect::<Vec<_>>();
+            attrs.push(("authors".to_string(), authors.join(", ")));
+            attrs.push(("authorcount".to_string(), authors.len().to_string()));
+        }
+        attrs
+    }
+}
+
+/// 修订信息，对应 `revnumber`、`revdate` 和 `revremark` 属性
+#[derive(Debug, Default, PartialEq)]
+pub struct Revision<'a> {
+    pub number: Option<&'a str>,
+    pub date: Option<&'a str>,
+    pub remark: Option<&'a str>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct AuthorInfo<'a> {
+    pub author: Name<'a>,
+    pub email: Option<&'a str>,
+}
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Name<'a> {
+    pub firstname: &'a str,
+    pub middle_name: Option<&'a str>,
+    pub lastname: Option<&'a str>,
+}
+
+impl Name<'_> {
+    /// 以空格连接的完整姓名
+    pub fn fullname(&self) -> String {
+        [Some(self.firstname), self.middle_name, self.lastname]
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// 姓名各部分的首字母
+    pub fn initials(&self) -> String {
+        [Some(self.firstname), self.middle_name, self.lastname]
+            .into_iter()
+            .flatten()
+            .filter_map(|part| part.chars().next())
+            .collect()
+    }
+}
+
+/// 章节，`level` 与标题中 `=` 的数量一致
+#[derive(Debug, PartialEq)]
+pub struct Section<'a> {
+    pub level: usize,
+    pub title: &'a str,
+    /// 显式给出的 id 或由标题生成的 id，`sectids` 被取消时没有生成的 id
+    pub id: Option<String>,
+    pub blocks: Vec<Block<'a>>,
+}
+
+/// 块，章节的范围从标题开始到最后一个子块结束
+#[derive(Debug, PartialEq)]
+pub struct Block<'a> {
+    pub kind: BlockKind<'a>,
+    pub metadata: BlockMetadata<'a>,
+    pub location: Location,
+}
+
+/// 块之前的属性列表（`[source,rust]`）、锚点（`[[id]]`）和块标题（`.Title`）
+///
+/// 第一个位置属性中的简写（`[quote#id.role%option]`）会被拆开：`quote` 留在
+/// `positional` 中，`id`、`role` 和 `option` 分别放入对应的字段。
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BlockMetadata<'a> {
+    pub id: Option<&'a str>,
+    pub title: Option<&'a str>,
+    pub roles: Vec<&'a str>,
+    pub options: Vec<&'a str>,
+    pub positional: Vec<&'a str>,
+    pub named: Vec<(&'a str, &'a str)>,
+}
+
+impl<'a> BlockMetadata<'a> {
+    /// 块的样式，即第一个位置属性
+    pub fn style(&self) -> Option<&'a str> {
+        self.positional.first().copied().filter(|style| !style.is_empty())
+    }
+
+    /// 查找命名属性，同名时后出现的优先
+    pub fn attribute(&self, name: &str) -> Option<&'a str> {
+        self.named.iter().rev().find(|(key, _)| *key == name).map(|(_, value)| *value)
+    }
+
+    pub fn has_option(&self, option: &str) -> bool {
+        self.options.contains(&option)
+    }
+
+    /// 合并同一个块之前的多行元数据，后出现的值覆盖先出现的值
+    pub fn merge(&mut self, other: BlockMetadata<'a>) {
+        self.id = other.id.or(self.id);
+        self.title = other.title.or(self.title);
+        self.roles.extend(other.roles);
+        self.options.extend(other.options);
+        for (index, value) in other.positional.into_iter().enumerate() {
+            // 空的位置属性（如 `[,rust]` 中的第一个）不覆盖已有的值
+            match self.positional.get_mut(index) {
+                Some(old) if !value.is_empty() => *old = value,
+                Some(_) => {}
+                None => self.positional.push(value),
+            }
+        }
+        self.named.extend(other.named);
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum BlockKind<'a> {
+    Section(Section<'a>),
+    Paragraph {
+        lines: &'a str,
+        inlines: Vec<Inline<'a>>,
+    },
+    /// `----`，内容原样保留，行尾的 `<1>` 等标注记录在 `callouts` 中
+    Listing {
+        content: &'a str,
+        callouts: Vec<Callout>,
+    },
+    /// `....`，内容原样保留
+    Literal { content: &'a str },
+    /// `++++`，内容不经任何处理直接输出
+    Passthrough { content: &'a str },
+    /// `====`
+    Example { blocks: Vec<Block<'a>> },
+    /// `****`
+    Sidebar { blocks: Vec<Block<'a>> },
+    /// `____`
+    Quote { blocks: Vec<Block<'a>> },
+    /// `--`
+    Open { blocks: Vec<Block<'a>> },
+    /// `*`、`-` 开头的无序列表，项中可以带有 `[x]` 或 `[ ]` 复选框
+    UnorderedList { items: Vec<ListItem<'a>> },
+    /// `.` 或 `1.` 开头的有序列表，`start` 为第一项显式给出的序号
+    OrderedList {
+        start: Option<usize>,
+        items: Vec<ListItem<'a>>,
+    },
+    /// `term:: 说明` 形式的描述列表，`[qanda]` 和 `[horizontal]` 样式只影响输出
+    DescriptionList { items: Vec<DescriptionItem<'a>> },
+    /// `NOTE: 文字` 段落，或者带有 `[NOTE]` 等样式的段落和 `====` 块
+    Admonition {
+        kind: AdmonitionKind,
+        /// 段落形式时只有一个段落
+        blocks: Vec<Block<'a>>,
+        /// 是否是段落形式，段落形式输出时不包裹段落的 `<div>`
+        paragraph: bool,
+    },
+    /// `|===` 表格，`,===` 和 `:===` 分别是 CSV 和 DSV 格式的简写
+    Table(Table<'a>),
+    /// 代码块之后的 `<1> 说明` 列表
+    CalloutList { items: Vec<CalloutItem<'a>> },
+}
+
+/// 列表项
+#[derive(Debug, PartialEq)]
+pub struct ListItem<'a> {
+    pub text: &'a str,
+    pub inlines: Vec<Inline<'a>>,
+    /// 清单项是否已完成，不是清单项时为 `None`
+    pub checked: Option<bool>,
+    /// 用 `+` 附加的块和嵌套的列表
+    pub blocks: Vec<Block<'a>>,
+    /// 列表标记和文字的范围
+    pub location: Location,
+}
+
+/// 提示的种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdmonitionKind {
+    Note,
+    Tip,
+    Important,
+    Warning,
+    Caution,
+}
+
+impl AdmonitionKind {
+    /// 由 `NOTE` 等大写标签得到种类
+    pub fn from_label(label: &str) -> Option<Self> {
+        match label {
+            "NOTE" => Some(AdmonitionKind::Note),
+            "TIP" => Some(AdmonitionKind::Tip),
+            "IMPORTANT" => Some(AdmonitionKind::Important),
+            "WARNING" => Some(AdmonitionKind::Warning),
+            "CAUTION" => Some(AdmonitionKind::Caution),
+            _ => None,
+        }
+    }
+
+    /// 小写的名称，用于 CSS 类名和 `note-caption` 等属性名
+    pub fn name(self) -> &'static str {
+        match self {
+            AdmonitionKind::Note => "note",
+            AdmonitionKind::Tip => "tip",
+            AdmonitionKind::Important => "important",
+            AdmonitionKind::Warning => "warning",
+            AdmonitionKind::Caution => "caution",
+        }
+    }
+
+    /// 默认的标题
+    pub fn caption(self) -> &'static str {
+        match self {
+            AdmonitionKind::Note => "Note",
+            AdmonitionKind::Tip => "Tip",
+            AdmonitionKind::Important => "Important",
+            AdmonitionKind::Warning => "Warning",
+            AdmonitionKind::Caution => "Caution",
+        }
+    }
+}
+
+/// 表格，表头和表尾都是可选的
+#[derive(Debug, PartialEq)]
+pub struct Table<'a> {
+    pub columns: Vec<Column>,
+    pub header: Option<Vec<Cell<'a>>>,
+    pub rows: Vec<Vec<Cell<'a>>>,
+    pub footer: Option<Vec<Cell<'a>>>,
+}
+
+/// `cols` 属性中的一列，例如 `2`、`^.>` 或 `3m`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Column {
+    /// 相对宽度
+    pub width: usize,
+    pub halign: HAlign,
+    pub valign: VAlign,
+    pub style: CellStyle,
+}
+
+impl Default for Column {
+    fn default() -> Self {
+        Column {
+            width: 1,
+            halign: HAlign::default(),
+            valign: VAlign::default(),
+            style: CellStyle::default(),
+        }
+    }
+}
+
+/// 水平对齐：`<`、`^`、`>`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum HAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+}
+
+/// 垂直对齐：`.<`、`.^`、`.>`
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum VAlign {
+    #[default]
+    Top,
+    Middle,
+    Bottom,
+}
+
+/// 单元格的样式，对应 `cols` 和单元格说明中的字母
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CellStyle {
+    /// `d`
+    #[default]
+    Default,
+    /// `a`，内容按 AsciiDoc 文档解析
+    AsciiDoc,
+    /// `e`
+    Emphasis,
+    /// `h`
+    Header,
+    /// `l`
+    Literal,
+    /// `m`
+    Monospace,
+    /// `s`
+    Strong,
+}
+
+/// 单元格，对齐方式和样式已经与所在列的设置合并
+#[derive(Debug, PartialEq)]
+pub struct Cell<'a> {
+    /// CSV 中带引号的值和含有转义的值不是原文的切片
+    pub text: Cow<'a, str>,
+    pub colspan: usize,
+    pub rowspan: usize,
+    pub halign: HAlign,
+    pub valign: VAlign,
+    pub style: CellStyle,
+    /// `a` 样式的单元格中解析出的块
+    pub blocks: Vec<Block<'a>>,
+    pub location: Location,
+}
+
+/// 描述列表中的一项，说明可以在术语的同一行，也可以在之后的行
+#[derive(Debug, PartialEq)]
+pub struct DescriptionItem<'a> {
+    pub term: &'a str,
+    pub text: Option<&'a str>,
+    pub inlines: Vec<Inline<'a>>,
+    /// 用 `+` 附加的块和嵌套的列表
+    pub blocks: Vec<Block<'a>>,
+    /// 术语和说明文字的范围
+    pub location: Location,
+}
+
+/// 代码块中的标注，例如行尾的 `<1>` 或 `// <1>`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Callout {
+    pub number: usize,
+    /// 标注在块内的行号，从 1 开始
+    pub line: usize,
+    /// 标注及其前面的注释符号的范围
+    pub location: Location,
+}
+
+/// 标注列表中的一项，`<.>` 按出现的顺序编号
+#[derive(Debug, PartialEq)]
+pub struct CalloutItem<'a> {
+    pub number: usize,
+    pub text: &'a str,
+    pub inlines: Vec<Inline<'a>>,
+    pub location: Location,
+}
+
+/// 行内节点
+#[derive(Eq, PartialEq, Debug)]
+pub struct Inline<'a> {
+    pub kind: InlineKind<'a>,
+    pub location: Location,
+}
+
+#[derive(Eq, PartialEq, Debug)]
+pub enum InlineKind<'a> {
+    Text(&'a str),
+    Formatted(FormattedText<'a>),
+    Xref(Xref<'a>),
+}
+
+/// 交叉引用：`<<id>>`、`<<id,text>>` 和 `xref:file.adoc#id[text]`
+#[derive(Eq, PartialEq, Debug)]
+pub struct Xref<'a> {
+    /// 引用其他文档时的路径
+    pub path: Option<&'a str>,
+    pub id: Option<&'a str>,
+    /// 没有给出文字时，解析引用之后填入目标的标题
+    pub text: Option<Cow<'a, str>>,
+}
+
+// Formatting pair
+#[derive(Eq, PartialEq, Debug)]
+pub enum FormattedText<'a> {
+    Strong(Vec<Inline<'a>>),
+    Emphasis(Vec<Inline<'a>>),
+    Monospace(Vec<Inline<'a>>),
+    Highlight(Vec<Inline<'a>>),
+    Superscript(Vec<Inline<'a>>),
+    Subscript(Vec<Inline<'a>>),
+}
+
+impl<'a> FormattedText<'a> {
+    /// 格式化文本中的行内节点
+    pub fn children(&self) -> &[Inline<'a>] {
+        match self {
+            FormattedText::Strong(children)
+            | FormattedText::Emphasis(children)
+            | FormattedText::Monospace(children)
+            | FormattedText::Highlight(children)
+            | FormattedText::Superscript(children)
+            | FormattedText::Subscript(children) => children,
+        }
+    }
+
+    pub fn children_mut(&mut self) -> &mut [Inline<'a>] {
+        match self {
+            FormattedText::Strong(children)
+            | FormattedText::Emphasis(children)
+            | FormattedText::Monospace(children)
+            | FormattedText::Highlight(children)
+            | FormattedText::Superscript(children)
+            | FormattedText::Subscript(children) => children,
+        }
+    }
+}
+
+#[derive(Eq, PartialEq, Debug)]
+pub struct Title<'a> {
+    pub level: usize,
+    pub content: &'a str,
+    pub location: Location,
+}
+
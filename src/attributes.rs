@@ -0,0 +1,339 @@
+//! 文档属性的解析与 `{name}` 引用替换
+
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use nom::bytes::complete::take_while;
+use nom::character::complete::{char, satisfy};
+use nom::combinator::recognize;
+use nom::sequence::{delimited, pair};
+use nom::IResult;
+
+use crate::ast::{Attribute, Document, Header};
+
+/// 引用了未定义属性时的处理方式，对应 `attribute-missing` 属性
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttributeMissing {
+    /// 保留引用原文
+    Skip,
+    /// 删除引用
+    Drop,
+    /// 删除引用所在的整行
+    DropLine,
+    /// 保留引用原文并给出警告
+    Warn,
+}
+
+impl FromStr for AttributeMissing {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "skip" => Ok(AttributeMissing::Skip),
+            "drop" => Ok(AttributeMissing::Drop),
+            "drop-line" => Ok(AttributeMissing::DropLine),
+            "warn" => Ok(AttributeMissing::Warn),
+            _ => Err(format!("unknown attribute-missing mode: {s}")),
+        }
+    }
+}
+
+/// 内置的字符替换属性，值是字符本身，由转换器负责转义
+const INTRINSIC_ATTRIBUTES: &[(&str, &str)] = &[
+    ("amp", "&"),
+    ("apos", "'"),
+    ("asterisk", "*"),
+    ("backslash", "\\"),
+    ("backtick", "`"),
+    ("blank", ""),
+    ("brvbar", "¦"),
+    ("caret", "^"),
+    ("cpp", "C++"),
+    ("empty", ""),
+    ("endsb", "]"),
+    ("gt", ">"),
+    ("lt", "<"),
+    ("nbsp", "\u{a0}"),
+    ("plus", "+"),
+    ("quot", "\""),
+    ("sp", " "),
+    ("startsb", "["),
+    ("tilde", "~"),
+    ("two-colons", "::"),
+    ("two-semicolons", ";;"),
+    ("vbar", "|"),
+    ("zwsp", "\u{200b}"),
+];
+
+/// 内置属性的默认值，文档可以修改
+const DEFAULT_ATTRIBUTES: &[(&str, &str)] = &[("attribute-missing", "skip")];
+
+/// 替换结果，`warnings` 只在 `warn` 模式下产生
+#[derive(Debug, PartialEq)]
+pub struct Substitution<'t> {
+    pub text: Cow<'t, str>,
+    pub warnings: Vec<String>,
+}
+
+/// 解析后的文档属性
+///
+/// 优先级从低到高：内置属性、作者行和修订行派生的属性、文档中的属性条目。
+/// 通过 [`Attributes::new`] 传入的覆盖属性会锁定对应的名称，文档无法再修改它们；
+/// 以 `@` 结尾的覆盖属性（如 `name=value@`）只作为默认值，文档仍可覆盖。
+#[derive(Debug, Default)]
+pub struct Attributes {
+    values: HashMap<String, String>,
+    locked: HashSet<String>,
+}
+
+impl Attributes {
+    /// 只包含内置属性和覆盖属性，预处理器用它收集文档中的属性条目
+    pub fn with_overrides(overrides: &[Attribute]) -> Self {
+        let mut attrs = Attributes::default();
+        for (name, value) in INTRINSIC_ATTRIBUTES.iter().chain(DEFAULT_ATTRIBUTES) {
+            attrs.values.insert(name.to_string(), value.to_string());
+        }
+        attrs.apply_overrides(overrides);
+        attrs
+    }
+
+    /// 设置覆盖属性，除了以 `@` 结尾的默认值都会锁定名称
+    pub fn apply_overrides(&mut self, overrides: &[Attribute]) {
+        for attr in overrides {
+            let soft_name = attr.name.strip_suffix('@');
+            let soft_value = attr.value.and_then(|value| value.strip_suffix('@'));
+            let name = soft_name.unwrap_or(attr.name).to_lowercase();
+
+            if attr.unset {
+                self.values.remove(&name);
+            } else {
+                let value = soft_value.or(attr.value).unwrap_or("");
+                self.values.insert(name.clone(), value.to_string());
+            }
+            if soft_name.is_none() && soft_value.is_none() {
+                self.locked.insert(name);
+            }
+        }
+    }
+
+    pub fn new(doc: &Document, overrides: &[Attribute]) -> Self {
+        let mut attrs = Attributes::with_overrides(overrides);
+        attrs.apply_document(doc);
+        attrs
+    }
+
+    /// 依次应用作者行、修订行和文档中的全部属性条目，忽略替换属性值时的警告
+    ///
+    /// 得到的是文档末尾的属性；需要按位置取值时（例如转换正文）应逐条调用 [`Attributes::apply`]。
+    pub fn apply_document(&mut self, doc: &Document) {
+        if let Some(header) = &doc.header {
+            self.apply_header(header);
+            for attr in &header.attrs {
+                self.apply(attr);
+            }
+        }
+
+        for attr in &doc.attrs {
+            self.apply(attr);
+        }
+    }
+
+    /// 应用文档标题、作者行和修订行派生的属性，不包括头部的属性条目
+    pub fn apply_header(&mut self, header: &Header) {
+        self.set("doctitle", header.title);
+        for (name, value) in header.author_attributes() {
+            self.set(&name, &value);
+        }
+        if let Some(revision) = &header.revision {
+            let values = [
+                ("revnumber", revision.number),
+                ("revdate", revision.date),
+                ("revremark", revision.remark),
+            ];
+            for (name, value) in values {
+                if let Some(value) = value {
+                    self.set(name, value);
+                }
+            }
+        }
+    }
+
+    /// 应用一条属性条目，值中的引用会先被替换，返回 `warn` 模式下替换时产生的警告
+    pub fn apply(&mut self, attr: &Attribute) -> Vec<String> {
+        if attr.unset {
+            let name = attr.name.to_lowercase();
+            if !self.locked.contains(&name) {
+                self.values.remove(&name);
+            }
+            vec![]
+        } else {
+            let substitution = self.substitute(attr.value.unwrap_or(""));
+            let value = substitution.text.into_owned();
+            self.set(attr.name, &value);
+            substitution.warnings
+        }
+    }
+
+    /// 设置属性，被锁定的属性保持不变
+    pub fn set(&mut self, name: &str, value: &str) {
+        let name = name.to_lowercase();
+        if !self.locked.contains(&name) {
+            self.values.insert(name, value.to_string());
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(&name.to_lowercase()).map(String::as_str)
+    }
+
+    pub fn missing(&self) -> AttributeMissing {
+        self.get("attribute-missing")
+            .and_then(|mode| mode.parse().ok())
+            .unwrap_or(AttributeMissing::Skip)
+    }
+
+    /// 替换文本中的 `{name}` 引用，`\{name}` 保持原样
+    pub fn substitute<'t>(&self, text: &'t str) -> Substitution<'t> {
+        if !text.contains('{') {
+            return Substitution {
+                text: Cow::Borrowed(text),
+                warnings: vec![],
+            };
+        }
+
+        let missing = self.missing();
+        let mut out = String::with_capacity(text.len());
+        let mut warnings = vec![];
+
+        for line in text.split_inclusive('\n') {
+            let start = out.len();
+            let mut rest = line;
+
+            while let Some(index) = rest.find('{') {
+                let (before, reference) = rest.split_at(index);
+                match parse_attribute_reference(reference) {
+                    Ok((after, _)) if before.ends_with('\\') => {
+                        out.push_str(&before[..before.len() - 1]);
+                        out.push_str(&reference[..reference.len() - after.len()]);
+                        rest = after;
+                    }
+                    Ok((after, name)) => {
+                        out.push_str(before);
+                        match (self.get(name), missing) {
+                            (Some(value), _) => out.push_str(value),
+                            (None, AttributeMissing::Skip) => {
+                                out.push_str(&reference[..reference.len() - after.len()]);
+                            }
+                            (None, AttributeMissing::Drop) => {}
+                            (None, AttributeMissing::DropLine) => {
+                                out.truncate(start);
+                                rest = "";
+                                break;
+                            }
+                            (None, AttributeMissing::Warn) => {
+                                warnings.push(format!("skipping reference to missing attribute: {name}"));
+                                out.push_str(&reference[..reference.len() - after.len()]);
+                            }
+                        }
+                        rest = after;
+                    }
+                    Err(_) => {
+                        out.push_str(before);
+                        out.push('{');
+                        rest = &reference[1..];
+                    }
+                }
+            }
+            out.push_str(rest);
+        }
+
+        // 删除最后一行时不保留前一行的换行符
+        if out.ends_with('\n') && !text.ends_with('\n') {
+            out.pop();
+        }
+
+        Substitution {
+            text: Cow::Owned(out),
+            warnings,
+        }
+    }
+}
+
+/// 解析 `{name}`，属性名由单词字符和 `-` 组成
+pub fn parse_attribute_reference(i: &str) -> IResult<&str, &str> {
+    delimited(
+        char('{'),
+        recognize(pair(
+            satisfy(|c| c.is_alphanumeric() || c == '_'),
+            take_while(|c: char| c.is_alphanumeric() || c == '_' || c == '-'),
+        )),
+        char('}'),
+    )(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::Attribute;
+    use crate::attributes::Attributes;
+    use crate::parser::parse_document;
+
+    #[test]
+    pub fn test_attribute_precedence() {
+        let doc = parse_document(
+            "= Doc\nAnn Author\nv1.2\n:product: Rusciidoc\n:edition: free\n:!flag:\n\n:body: {product} {edition}\n",
+        )
+        .unwrap();
+        let overrides = [
+            Attribute::from_override("edition=pro"),
+            Attribute::from_override("flag"),
+            Attribute::from_override("product=Default@"),
+        ];
+        let attrs = Attributes::new(&doc, &overrides);
+
+        assert_eq!(attrs.get("doctitle"), Some("Doc"));
+        assert_eq!(attrs.get("author"), Some("Ann Author"));
+        assert_eq!(attrs.get("revnumber"), Some("1.2"));
+        assert_eq!(attrs.get("product"), Some("Rusciidoc"));
+        assert_eq!(attrs.get("edition"), Some("pro"));
+        assert_eq!(attrs.get("flag"), Some(""));
+        assert_eq!(attrs.get("body"), Some("Rusciidoc pro"));
+    }
+
+    #[test]
+    pub fn test_substitute() {
+        let doc = parse_document("= Doc\n:name: World\n").unwrap();
+        let attrs = Attributes::new(&doc, &[]);
+
+        assert_eq!(attrs.substitute("Hello, {name}!").text, "Hello, World!");
+        assert_eq!(attrs.substitute("Hello, \\{name}!").text, "Hello, {name}!");
+        assert_eq!(attrs.substitute("{ not a ref } {lt}").text, "{ not a ref } <");
+        assert_eq!(attrs.substitute("keep {missing}").text, "keep {missing}");
+    }
+
+    #[test]
+    pub fn test_attribute_missing_modes() {
+        let text = "first {name}\nsecond {missing}\nthird";
+
+        let doc = parse_document("= Doc\n:name: x\n:attribute-missing: drop\n").unwrap();
+        let result = Attributes::new(&doc, &[]).substitute(text);
+        assert_eq!(result.text, "first x\nsecond \nthird");
+
+        let doc = parse_document("= Doc\n:name: x\n:attribute-missing: drop-line\n").unwrap();
+        let result = Attributes::new(&doc, &[]).substitute(text);
+        assert_eq!(result.text, "first x\nthird");
+
+        let result = Attributes::new(&doc, &[]).substitute("only {missing}");
+        assert_eq!(result.text, "");
+
+        let doc = parse_document("= Doc\n:name: x\n:attribute-missing: warn\n").unwrap();
+        let result = Attributes::new(&doc, &[]).substitute(text);
+        assert_eq!(result.text, "first x\nsecond {missing}\nthird");
+        assert_eq!(result.warnings, vec!["skipping reference to missing attribute: missing"]);
+
+        let mut attrs = Attributes::new(&doc, &[]);
+        let doc = parse_document(":copy: {missing}\n").unwrap();
+        assert_eq!(attrs.apply(&doc.attrs[0]), vec!["skipping reference to missing attribute: missing"]);
+        assert_eq!(attrs.get("copy"), Some("{missing}"));
+    }
+}
@@ -0,0 +1,128 @@
+//! `[source]` 代码块的语法高亮
+//!
+//! 启用 `highlight` 特性时使用 syntect 内置的语法和主题为代码着色，
+//! 否则只转义 HTML 特殊字符。行号和高亮行在两种情况下都会输出。
+
+use std::ops::RangeInclusive;
+
+use crate::converter::html::escape;
+
+/// 默认的 syntect 主题，可以通过 `syntect-style` 属性修改
+pub const DEFAULT_STYLE: &str = "InspiredGitHub";
+
+/// 将代码按行转换为 HTML，每一项都保留行尾的换行符
+///
+/// `language` 为空或者不认识时返回 `None`。
+#[cfg(feature = "highlight")]
+pub fn highlight(code: &str, language: &str, style: &str) -> Option<Vec<String>> {
+    use std::sync::OnceLock;
+
+    use syntect::easy::HighlightLines;
+    use syntect::highlighting::ThemeSet;
+    use syntect::html::{styled_line_to_highlighted_html, IncludeBackground};
+    use syntect::parsing::SyntaxSet;
+    use syntect::util::LinesWithEndings;
+
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    static THEMES: OnceLock<ThemeSet> = OnceLock::new();
+
+    let syntaxes = SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines);
+    let themes = THEMES.get_or_init(ThemeSet::load_defaults);
+
+    let syntax = syntaxes.find_syntax_by_token(language)?;
+    let theme = themes.themes.get(style).or_else(|| themes.themes.get(DEFAULT_STYLE))?;
+
+    let mut highlighter = HighlightLines::new(syntax, theme);
+    LinesWithEndings::from(code)
+        .map(|line| {
+            let ranges = highlighter.highlight_line(line, syntaxes).ok()?;
+            styled_line_to_highlighted_html(&ranges, IncludeBackground::No).ok()
+        })
+        .collect()
+}
+
+#[cfg(not(feature = "highlight"))]
+pub fn highlight(_code: &str, _language: &str, _style: &str) -> Option<Vec<String>> {
+    None
+}
+
+/// 解析 `highlight` 属性，例如 `2..4`、`1,3..5` 或 `1;3-5`
+pub fn parse_line_ranges(spec: &str) -> Vec<RangeInclusive<usize>> {
+    spec.split([',', ';'])
+        .filter_map(|entry| {
+            let entry = entry.trim();
+            let (start, end) = entry
+                .split_once("..")
+                .or_else(|| entry.split_once('-'))
+                .unwrap_or((entry, entry));
+            Some(start.trim().parse().ok()?..=end.trim().parse().ok()?)
+        })
+        .collect()
+}
+
+/// 不做语法高亮时按行转义代码，每一项都保留行尾的换行符
+pub fn escape_lines(code: &str) -> Vec<String> {
+    code.split_inclusive('\n').map(escape).collect()
+}
+
+/// 输出代码的各行，`linenums` 为 `true` 时在每行之前加上行号，`highlight` 中的行用
+/// `<span class="hll">` 包裹
+pub fn format_lines(lines: &[String], linenums: bool, highlight: &[RangeInclusive<usize>]) -> String {
+    let width = lines.len().to_string().len();
+
+    let mut out = String::new();
+    for (index, line) in lines.iter().enumerate() {
+        let number = index + 1;
+        let highlighted = highlight.iter().any(|range| range.contains(&number));
+        if highlighted {
+            out.push_str("<span class=\"hll\">");
+        }
+        if linenums {
+            out.push_str(&format!("<span class=\"linenos\">{number:>width$}</span>"));
+        }
+        out.push_str(line);
+        if highlighted {
+            out.push_str("</span>");
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::converter::highlight::{escape_lines, format_lines, parse_line_ranges};
+
+    #[test]
+    pub fn test_parse_line_ranges() {
+        assert_eq!(parse_line_ranges("2..4"), vec![2..=4]);
+        assert_eq!(parse_line_ranges("1, 3..5"), vec![1..=1, 3..=5]);
+        assert_eq!(parse_line_ranges("1;3-5"), vec![1..=1, 3..=5]);
+        assert_eq!(parse_line_ranges("x"), vec![]);
+    }
+
+    #[test]
+    pub fn test_format_lines() {
+        let lines = escape_lines("a < b\nc\nd");
+        assert_eq!(format_lines(&lines, false, &[]), "a &lt; b\nc\nd");
+        assert_eq!(
+            format_lines(&lines, true, &[2..=2]),
+            "<span class=\"linenos\">1</span>a &lt; b\n\
+             <span class=\"hll\"><span class=\"linenos\">2</span>c\n</span>\
+             <span class=\"linenos\">3</span>d"
+        );
+    }
+
+    #[cfg(feature = "highlight")]
+    #[test]
+    pub fn test_highlight() {
+        use crate::converter::highlight::{highlight, DEFAULT_STYLE};
+
+        let lines = highlight("fn main() {}\nlet x = 1;\n", "rust", DEFAULT_STYLE).unwrap();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("<span style="));
+        assert!(lines[0].contains(">main</span>"));
+        assert!(lines[0].contains('\n'));
+
+        assert_eq!(highlight("code", "no-such-language", DEFAULT_STYLE), None);
+    }
+}
@@ -0,0 +1,1137 @@
+//! 生成与 Asciidoctor 默认 HTML5 结构兼容的输出
+
+use std::collections::HashMap;
+
+use crate::ast::{
+    Attribute, Block, BlockKind, BlockMetadata, Callout, Cell, CellStyle, DescriptionItem, Document, FormattedText, HAlign, Header,
+    Inline, InlineKind, ListItem, Position, Revision, Section, Table, VAlign, Xref,
+};
+use crate::diagnostic::Diagnostic;
+use crate::attributes::Attributes;
+use crate::converter::highlight::{escape_lines, format_lines, highlight, parse_line_ranges, DEFAULT_STYLE};
+use crate::converter::{Options, Output};
+use crate::parser::callout::split_callouts;
+use crate::parser::inline::parse_inlines;
+use crate::parser::{locate, Span};
+use crate::safe_mode::SafeMode;
+
+/// 生成 HTML，`options.embedded` 为 `false` 时生成包含 `<head>` 的完整页面
+pub fn convert(doc: &Document, options: &Options) -> Output {
+    let mut attrs = Attributes::with_overrides(&options.attributes);
+    attrs.apply_overrides(&options.safe_mode.attributes());
+
+    let mut warnings = vec![];
+    // 头部的属性条目先于所有块生效，正文中的条目在转换到它所在的位置时才生效
+    if let Some(header) = &doc.header {
+        attrs.apply_header(header);
+        for attr in &header.attrs {
+            warnings.extend(attrs.apply(attr).into_iter().map(|message| Diagnostic::warning(message, attr.location)));
+        }
+    }
+    let entries = doc.header.iter().flat_map(|header| header.attrs.iter()).chain(&doc.attrs);
+    for attr in entries.filter(|attr| options.safe_mode.locks(attr.name)) {
+        let message = format!("attribute {} is locked in {} mode", attr.name, options.safe_mode);
+        warnings.push(Diagnostic::warning(message, attr.location));
+    }
+
+    let mut writer = HtmlWriter {
+        out: String::new(),
+        attrs,
+        entries: &doc.attrs,
+        warnings,
+        safe_mode: options.safe_mode,
+        source: doc.source,
+        references: &doc.references,
+        in_xref: false,
+        callout_blocks: 0,
+        ordered_depth: 0,
+        tables: 0,
+    };
+    if options.embedded {
+        writer.content(doc);
+    } else {
+        writer.document(doc);
+    }
+    // 最后一个块之后的属性条目不影响输出，但同样检查其中的引用
+    writer.advance(Position { line: usize::MAX, column: 0 });
+    Output {
+        content: writer.out,
+        warnings: writer.warnings,
+    }
+}
+
+struct HtmlWriter<'s> {
+    out: String,
+    attrs: Attributes,
+    /// 尚未应用的正文属性条目
+    entries: &'s [Attribute<'s>],
+    warnings: Vec<Diagnostic>,
+    safe_mode: SafeMode,
+    source: &'s str,
+    references: &'s HashMap<String, Option<String>>,
+    /// 是否正在输出交叉引用的文字，其中的交叉引用不再生成链接
+    in_xref: bool,
+    /// 已输出的带标注的代码块数量，用于生成标注列表项的 id
+    callout_blocks: usize,
+    /// 正在输出的有序列表的嵌套深度，决定默认的序号样式
+    ordered_depth: usize,
+    /// 已输出的带标题的表格数量，用于生成 `Table 1.` 这样的标题前缀
+    tables: usize,
+}
+
+impl HtmlWriter<'_> {
+    fn document(&mut self, doc: &Document) {
+        let lang = escape(self.attrs.get("lang").unwrap_or("en"));
+        self.out.push_str(&format!("<!DOCTYPE html>\n<html lang=\"{lang}\">\n<head>\n"));
+        self.out.push_str("<meta charset=\"UTF-8\">\n");
+        self.out.push_str(
+            "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1.0\">\n",
+        );
+        let authors = doc
+            .header
+            .iter()
+            .flat_map(|header| header.auth_info.iter())
+            .map(|info| info.author.fullname())
+            .collect::<Vec<_>>();
+        if !authors.is_empty() {
+            self.out.push_str(&format!(
+                "<meta name=\"author\" content=\"{}\">\n",
+                escape(&authors.join(", ")),
+            ));
+        }
+        if let Some(header) = &doc.header {
+            let title = self.substitute(header.title);
+            self.out.push_str(&format!("<title>{}</title>\n", escape(&title)));
+        }
+        self.out.push_str("</head>\n<body class=\"article\">\n");
+
+        if let Some(header) = &doc.header {
+            self.header(header);
+        }
+        self.out.push_str("<div id=\"content\">\n");
+        self.content(doc);
+        self.out.push_str("</div>\n</body>\n</html>\n");
+    }
+
+    fn header(&mut self, header: &Header) {
+        self.out.push_str("<div id=\"header\">\n<h1>");
+        self.text(header.title);
+        self.out.push_str("</h1>\n");
+
+        if !header.auth_info.is_empty() {
+            self.out.push_str("<div class=\"details\">\n");
+            for (index, author) in header.auth_info.iter().enumerate() {
+                // 从第二个作者开始，id 带有序号后缀
+                let suffix = if index == 0 { String::new() } else { (index + 1).to_string() };
+                self.out.push_str(&format!(
+                    "<span id=\"author{suffix}\" class=\"author\">{}</span><br>\n",
+                    escape(&author.author.fullname()),
+                ));
+                if let Some(email) = author.email {
+                    let email = escape(email);
+                    self.out.push_str(&format!(
+                        "<span id=\"email{suffix}\" class=\"email\"><a href=\"mailto:{email}\">{email}</a></span><br>\n",
+                    ));
+                }
+            }
+            if let Some(revision) = &header.revision {
+                self.revision(revision);
+            }
+            self.out.push_str("</div>\n");
+        }
+        self.out.push_str("</div>\n");
+    }
+
+    fn revision(&mut self, revision: &Revision) {
+        if let Some(number) = revision.number {
+            let separator = if revision.date.is_some() { "," } else { "" };
+            self.out.push_str(&format!(
+                "<span id=\"revnumber\">version {}{separator}</span>\n",
+                escape(number),
+            ));
+        }
+        if let Some(date) = revision.date {
+            self.out.push_str(&format!("<span id=\"revdate\">{}</span>\n", escape(date)));
+        }
+        if let Some(remark) = revision.remark {
+            self.out.push_str(&format!(
+                "<br><span id=\"revremark\">{}</span>\n",
+                escape(remark),
+            ));
+        }
+    }
+
+    /// 章节之前的块放在 `#preamble` 中，与 Asciidoctor 一致
+    fn content(&mut self, doc: &Document) {
+        let preamble_len = doc
+            .blocks
+            .iter()
+            .position(|block| matches!(block.kind, BlockKind::Section(_)))
+            .unwrap_or(doc.blocks.len());
+
+        if doc.header.is_some() && preamble_len > 0 && preamble_len < doc.blocks.len() {
+            self.out.push_str("<div id=\"preamble\">\n<div class=\"sectionbody\">\n");
+            self.blocks(&doc.blocks[..preamble_len]);
+            self.out.push_str("</div>\n</div>\n");
+            self.blocks(&doc.blocks[preamble_len..]);
+        } else {
+            self.blocks(&doc.blocks);
+        }
+    }
+
+    fn blocks(&mut self, blocks: &[Block]) {
+        for (index, block) in blocks.iter().enumerate() {
+            self.check_callouts(blocks, index);
+            self.block(block);
+        }
+    }
+
+    /// 代码块中的标注和紧随其后的标注列表应当一一对应，否则给出警告
+    fn check_callouts(&mut self, blocks: &[Block], index: usize) {
+        match &blocks[index].kind {
+            BlockKind::Listing { callouts, .. } => {
+                let items = match blocks.get(index + 1).map(|block| &block.kind) {
+                    Some(BlockKind::CalloutList { items }) => items.as_slice(),
+                    _ => &[],
+                };
+                for (position, callout) in callouts.iter().enumerate() {
+                    let repeated = callouts[..position].iter().any(|other| other.number == callout.number);
+                    if !repeated && !items.iter().any(|item| item.number == callout.number) {
+                        self.warnings.push(Diagnostic::warning(
+                            format!("no callout list item found for <{}>", callout.number),
+                            callout.location,
+                        ));
+                    }
+                }
+            }
+            BlockKind::CalloutList { items } => {
+                let callouts = match index.checked_sub(1).map(|previous| &blocks[previous].kind) {
+                    Some(BlockKind::Listing { callouts, .. }) => callouts.as_slice(),
+                    _ => &[],
+                };
+                for item in items {
+                    if !callouts.iter().any(|callout| callout.number == item.number) {
+                        self.warnings.push(Diagnostic::warning(
+                            format!("no callout found for <{}>", item.number),
+                            item.location,
+                        ));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// 应用 `position` 之前的正文属性条目，块按文档顺序访问
+    fn advance(&mut self, position: Position) {
+        while let Some((attr, rest)) = self.entries.split_first() {
+            if attr.location.start >= position {
+                break;
+            }
+            for message in self.attrs.apply(attr) {
+                self.warnings.push(Diagnostic::warning(message, attr.location));
+            }
+            self.entries = rest;
+        }
+    }
+
+    fn block(&mut self, block: &Block) {
+        self.advance(block.location.start);
+        let metadata = &block.metadata;
+        match &block.kind {
+            BlockKind::Section(section) => self.section(section, metadata),
+            BlockKind::Paragraph { inlines, .. } => {
+                self.open_block("paragraph", metadata);
+                self.block_title(metadata);
+                self.out.push_str("<p>");
+                self.inlines(inlines);
+                self.out.push_str("</p>\n</div>\n");
+            }
+            BlockKind::Listing { content, callouts } if metadata.style() == Some("source") => {
+                self.source(metadata, content, callouts)
+            }
+            BlockKind::Listing { content, callouts } => self.verbatim("listingblock", metadata, content, callouts),
+            BlockKind::Literal { content } => self.verbatim("literalblock", metadata, content, &[]),
+            BlockKind::Passthrough { content } if self.safe_mode >= SafeMode::Server => {
+                let message = format!("passthrough block is escaped in {} mode", self.safe_mode);
+                self.warnings.push(Diagnostic::warning(message, block.location));
+                self.verbatim("literalblock", metadata, content, &[]);
+            }
+            BlockKind::Passthrough { content } => {
+                self.out.push_str(content);
+                self.out.push('\n');
+            }
+            BlockKind::Example { blocks } => self.compound("exampleblock", metadata, blocks),
+            BlockKind::Open { blocks } => self.compound("openblock", metadata, blocks),
+            BlockKind::Sidebar { blocks } => {
+                // 侧边栏的标题在内容之中
+                self.open_block("sidebarblock", metadata);
+                self.out.push_str("<div class=\"content\">\n");
+                self.block_title(metadata);
+                self.blocks(blocks);
+                self.out.push_str("</div>\n</div>\n");
+            }
+            BlockKind::Quote { blocks } => {
+                self.open_block("quoteblock", metadata);
+                self.block_title(metadata);
+                self.out.push_str("<blockquote>\n");
+                self.blocks(blocks);
+                self.out.push_str("</blockquote>\n");
+                self.attribution(metadata);
+                self.out.push_str("</div>\n");
+            }
+            BlockKind::UnorderedList { items } => {
+                let checklist = items.iter().any(|item| item.checked.is_some());
+                self.open_block(if checklist { "ulist checklist" } else { "ulist" }, metadata);
+                self.block_title(metadata);
+                self.out.push_str(if checklist { "<ul class=\"checklist\">\n" } else { "<ul>\n" });
+                self.list_items(items);
+                self.out.push_str("</ul>\n</div>\n");
+            }
+            BlockKind::OrderedList { start, items } => self.ordered_list(metadata, *start, items),
+            BlockKind::DescriptionList { items } => match metadata.style() {
+                Some("qanda") => self.qanda_list(metadata, items),
+                Some("horizontal") => self.horizontal_list(metadata, items),
+                _ => self.description_list(metadata, items),
+            },
+            BlockKind::Admonition { kind, blocks, paragraph } => {
+                self.open_block(&format!("admonitionblock {}", kind.name()), metadata);
+                let caption = self.attrs.get(&format!("{}-caption", kind.name())).unwrap_or(kind.caption());
+                self.out.push_str(&format!(
+                    "<table>\n<tr>\n<td class=\"icon\">\n<div class=\"title\">{}</div>\n</td>\n<td class=\"content\">\n",
+                    escape(caption),
+                ));
+                self.block_title(metadata);
+                match blocks.as_slice() {
+                    // 段落形式只输出文字
+                    [Block { kind: BlockKind::Paragraph { inlines, .. }, .. }] if *paragraph => {
+                        self.inlines(inlines);
+                        self.out.push('\n');
+                    }
+                    blocks => self.blocks(blocks),
+                }
+                self.out.push_str("</td>\n</tr>\n</table>\n</div>\n");
+            }
+            BlockKind::Table(table) => self.table(metadata, table),
+            BlockKind::CalloutList { items } => {
+                self.open_block("colist arabic", metadata);
+                self.block_title(metadata);
+                self.out.push_str("<ol>\n");
+                for item in items {
+                    self.out.push_str(&format!("<li id=\"CO{}-{}\">\n<p>", self.callout_blocks, item.number));
+                    self.inlines(&item.inlines);
+                    self.out.push_str("</p>\n</li>\n");
+                }
+                self.out.push_str("</ol>\n</div>\n");
+            }
+        }
+    }
+
+    /// 有序列表的序号样式随嵌套深度变化，也可以用 `[loweralpha]` 等样式指定
+    fn ordered_list(&mut self, metadata: &BlockMetadata, start: Option<usize>, items: &[ListItem]) {
+        const STYLES: [(&str, &str); 5] = [
+            ("arabic", ""),
+            ("loweralpha", "a"),
+            ("lowerroman", "i"),
+            ("upperalpha", "A"),
+            ("upperroman", "I"),
+        ];
+        let style = metadata.style().unwrap_or(STYLES[self.ordered_depth % STYLES.len()].0);
+        let style = escape(style);
+
+        self.open_block(&format!("olist {style}"), metadata);
+        self.block_title(metadata);
+        self.out.push_str(&format!("<ol class=\"{style}\""));
+        if let Some((_, kind)) = STYLES.iter().find(|(name, kind)| *name == style && !kind.is_empty()) {
+            self.out.push_str(&format!(" type=\"{kind}\""));
+        }
+        let start = metadata.attribute("start").map(str::to_string).or(start.map(|start| start.to_string()));
+        if let Some(start) = start {
+            self.out.push_str(&format!(" start=\"{}\"", escape(&start)));
+        }
+        if metadata.has_option("reversed") {
+            self.out.push_str(" reversed");
+        }
+        self.out.push_str(">\n");
+
+        self.ordered_depth += 1;
+        self.list_items(items);
+        self.ordered_depth -= 1;
+        self.out.push_str("</ol>\n</div>\n");
+    }
+
+    fn list_items(&mut self, items: &[ListItem]) {
+        for item in items {
+            self.out.push_str("<li>\n<p>");
+            match item.checked {
+                Some(true) => self.out.push_str("&#10003; "),
+                Some(false) => self.out.push_str("&#10063; "),
+                None => {}
+            }
+            self.inlines(&item.inlines);
+            self.out.push_str("</p>\n");
+            self.blocks(&item.blocks);
+            self.out.push_str("</li>\n");
+        }
+    }
+
+    fn description_list(&mut self, metadata: &BlockMetadata, items: &[DescriptionItem]) {
+        self.open_block("dlist", metadata);
+        self.block_title(metadata);
+        self.out.push_str("<dl>\n");
+        for item in items {
+            self.out.push_str("<dt class=\"hdlist1\">");
+            self.text(item.term);
+            self.out.push_str("</dt>\n");
+            // 没有说明的术语不输出 `<dd>`
+            if item.text.is_some() || !item.blocks.is_empty() {
+                self.out.push_str("<dd>\n");
+                self.description(item);
+                self.out.push_str("</dd>\n");
+            }
+        }
+        self.out.push_str("</dl>\n</div>\n");
+    }
+
+    /// `[qanda]`：术语是问题，说明是答案
+    fn qanda_list(&mut self, metadata: &BlockMetadata, items: &[DescriptionItem]) {
+        self.open_block("qlist qanda", metadata);
+        self.block_title(metadata);
+        self.out.push_str("<ol>\n");
+        for item in items {
+            self.out.push_str("<li>\n<p><em>");
+            self.text(item.term);
+            self.out.push_str("</em></p>\n");
+            self.description(item);
+            self.out.push_str("</li>\n");
+        }
+        self.out.push_str("</ol>\n</div>\n");
+    }
+
+    /// `[horizontal]`：术语和说明并排放在表格的两列中
+    fn horizontal_list(&mut self, metadata: &BlockMetadata, items: &[DescriptionItem]) {
+        self.open_block("hdlist", metadata);
+        self.block_title(metadata);
+        self.out.push_str("<table>\n");
+        for item in items {
+            self.out.push_str("<tr>\n<td class=\"hdlist1\">\n");
+            self.text(item.term);
+            self.out.push_str("\n</td>\n<td class=\"hdlist2\">\n");
+            self.description(item);
+            self.out.push_str("</td>\n</tr>\n");
+        }
+        self.out.push_str("</table>\n</div>\n");
+    }
+
+    fn table(&mut self, metadata: &BlockMetadata, table: &Table) {
+        self.out.push_str("<table");
+        if let Some(id) = metadata.id {
+            self.out.push_str(&format!(" id=\"{}\"", escape(id)));
+        }
+        let classes = ["tableblock", "frame-all", "grid-all", "stretch"].into_iter().chain(metadata.roles.iter().copied());
+        self.out.push_str(&format!(" class=\"{}\">\n", escape(&classes.collect::<Vec<_>>().join(" "))));
+        if let Some(title) = metadata.title {
+            self.tables += 1;
+            self.out.push_str(&format!("<caption class=\"title\">Table {}. ", self.tables));
+            self.text(title);
+            self.out.push_str("</caption>\n");
+        }
+
+        // 列宽按相对宽度换算成百分比，最后一列补足剩余部分
+        self.out.push_str("<colgroup>\n");
+        let total = table.columns.iter().map(|column| column.width).sum::<usize>().max(1) as f64;
+        let mut used = 0.0;
+        for (index, column) in table.columns.iter().enumerate() {
+            let width = if index + 1 == table.columns.len() {
+                100.0 - used
+            } else {
+                (column.width as f64 * 100.0 / total * 10000.0).round() / 10000.0
+            };
+            used += width;
+            let width = format!("{width:.4}");
+            let width = width.trim_end_matches('0').trim_end_matches('.');
+            self.out.push_str(&format!("<col style=\"width: {width}%;\">\n"));
+        }
+        self.out.push_str("</colgroup>\n");
+
+        if let Some(header) = &table.header {
+            self.out.push_str("<thead>\n");
+            self.table_row(header, true);
+            self.out.push_str("</thead>\n");
+        }
+        self.out.push_str("<tbody>\n");
+        for row in &table.rows {
+            self.table_row(row, false);
+        }
+        self.out.push_str("</tbody>\n");
+        if let Some(footer) = &table.footer {
+            self.out.push_str("<tfoot>\n");
+            self.table_row(footer, false);
+            self.out.push_str("</tfoot>\n");
+        }
+        self.out.push_str("</table>\n");
+    }
+
+    fn table_row(&mut self, cells: &[Cell], header: bool) {
+        self.out.push_str("<tr>\n");
+        for cell in cells {
+            let tag = if header || cell.style == CellStyle::Header { "th" } else { "td" };
+            let halign = match cell.halign {
+                HAlign::Left => "left",
+                HAlign::Center => "center",
+                HAlign::Right => "right",
+            };
+            let valign = match cell.valign {
+                VAlign::Top => "top",
+                VAlign::Middle => "middle",
+                VAlign::Bottom => "bottom",
+            };
+            self.out.push_str(&format!("<{tag} class=\"tableblock halign-{halign} valign-{valign}\""));
+            if cell.colspan > 1 {
+                self.out.push_str(&format!(" colspan=\"{}\"", cell.colspan));
+            }
+            if cell.rowspan > 1 {
+                self.out.push_str(&format!(" rowspan=\"{}\"", cell.rowspan));
+            }
+            self.out.push('>');
+            if header {
+                self.text(&cell.text);
+            } else {
+                self.table_cell(cell);
+            }
+            self.out.push_str(&format!("</{tag}>\n"));
+        }
+        self.out.push_str("</tr>\n");
+    }
+
+    /// 单元格的内容，空行分隔的每一段都是一个段落
+    fn table_cell(&mut self, cell: &Cell) {
+        let (open, close) = match cell.style {
+            CellStyle::AsciiDoc => {
+                self.out.push_str("<div class=\"content\">\n");
+                self.blocks(&cell.blocks);
+                self.out.push_str("</div>");
+                return;
+            }
+            CellStyle::Literal => {
+                self.out.push_str("<div class=\"literal\"><pre>");
+                self.out.push_str(&escape(&cell.text));
+                self.out.push_str("</pre></div>");
+                return;
+            }
+            CellStyle::Emphasis => ("<em>", "</em>"),
+            CellStyle::Monospace => ("<code>", "</code>"),
+            CellStyle::Strong => ("<strong>", "</strong>"),
+            CellStyle::Default | CellStyle::Header => ("", ""),
+        };
+        for paragraph in cell.text.split("\n\n").map(str::trim).filter(|paragraph| !paragraph.is_empty()) {
+            self.out.push_str(&format!("<p class=\"tableblock\">{open}"));
+            self.text(paragraph);
+            self.out.push_str(&format!("{close}</p>"));
+        }
+    }
+
+    /// 描述列表项的说明文字和附加的块
+    fn description(&mut self, item: &DescriptionItem) {
+        if item.text.is_some() {
+            self.out.push_str("<p>");
+            self.inlines(&item.inlines);
+            self.out.push_str("</p>\n");
+        }
+        self.blocks(&item.blocks);
+    }
+
+    /// 块最外层的 `<div>`，带有 id 和角色
+    fn open_block(&mut self, class: &str, metadata: &BlockMetadata) {
+        self.out.push_str("<div");
+        if let Some(id) = metadata.id {
+            self.out.push_str(&format!(" id=\"{}\"", escape(id)));
+        }
+        let classes = std::iter::once(class).chain(metadata.roles.iter().copied());
+        self.out.push_str(&format!(" class=\"{}\">\n", escape(&classes.collect::<Vec<_>>().join(" "))));
+    }
+
+    fn block_title(&mut self, metadata: &BlockMetadata) {
+        if let Some(title) = metadata.title {
+            self.out.push_str("<div class=\"title\">");
+            self.text(title);
+            self.out.push_str("</div>\n");
+        }
+    }
+
+    /// `[quote, 作者, 出处]` 中的作者和出处
+    fn attribution(&mut self, metadata: &BlockMetadata) {
+        let attribution = metadata.attribute("attribution").or(metadata.positional.get(1).copied());
+        let citetitle = metadata.attribute("citetitle").or(metadata.positional.get(2).copied());
+        if attribution.is_none() && citetitle.is_none() {
+            return;
+        }
+
+        self.out.push_str("<div class=\"attribution\">\n");
+        if let Some(attribution) = attribution {
+            self.out.push_str("&#8212; ");
+            self.text(attribution);
+            self.out.push_str(if citetitle.is_some() { "<br>\n" } else { "\n" });
+        }
+        if let Some(citetitle) = citetitle {
+            self.out.push_str("<cite>");
+            self.text(citetitle);
+            self.out.push_str("</cite>\n");
+        }
+        self.out.push_str("</div>\n");
+    }
+
+    /// 原样输出的块，只转义 HTML 特殊字符
+    fn verbatim(&mut self, class: &str, metadata: &BlockMetadata, content: &str, callouts: &[Callout]) {
+        let (lines, _) = self.code_lines(content, "", callouts);
+        self.open_block(class, metadata);
+        self.block_title(metadata);
+        self.out.push_str("<div class=\"content\">\n<pre>");
+        self.out.push_str(&lines.concat());
+        self.out.push_str("</pre>\n</div>\n</div>\n");
+    }
+
+    /// 代码块的各行，标注先从代码中去掉，高亮之后再以链接的形式放回行尾
+    ///
+    /// 第二个返回值表示是否做了语法高亮。
+    fn code_lines(&mut self, content: &str, language: &str, callouts: &[Callout]) -> (Vec<String>, bool) {
+        if callouts.is_empty() {
+            let lines = self.highlight(content, language);
+            let highlighted = lines.is_some();
+            return (lines.unwrap_or_else(|| escape_lines(content)), highlighted);
+        }
+
+        self.callout_blocks += 1;
+        let split = split_callouts(content);
+        let code = split.iter().map(|(code, _)| *code).collect::<Vec<_>>().join("\n");
+        let lines = self.highlight(&code, language);
+        let highlighted = lines.is_some();
+        let mut lines = lines.unwrap_or_else(|| escape_lines(&code));
+        for (line, (_, numbers)) in lines.iter_mut().zip(split) {
+            let markers: String = numbers
+                .iter()
+                .map(|number| {
+                    format!(
+                        " <a href=\"#CO{}-{number}\"><b class=\"conum\">({number})</b></a>",
+                        self.callout_blocks,
+                    )
+                })
+                .collect();
+            let end = line.rfind('\n').unwrap_or(line.len());
+            line.insert_str(end, &markers);
+        }
+        (lines, highlighted)
+    }
+
+    fn highlight(&self, code: &str, language: &str) -> Option<Vec<String>> {
+        if language.is_empty() {
+            return None;
+        }
+        let style = self.attrs.get("syntect-style").unwrap_or(DEFAULT_STYLE);
+        highlight(code, language, style)
+    }
+
+    /// `[source,语言]` 代码块，可以带有 `linenums` 选项和 `highlight` 属性
+    fn source(&mut self, metadata: &BlockMetadata, content: &str, callouts: &[Callout]) {
+        let language = metadata
+            .positional
+            .get(1)
+            .copied()
+            .or(metadata.attribute("language"))
+            .or(self.attrs.get("source-language"))
+            .unwrap_or("")
+            .to_string();
+        let linenums = metadata.has_option("linenums")
+            || metadata.positional.get(2) == Some(&"linenums")
+            || metadata.attribute("linenums").is_some();
+        let ranges = metadata.attribute("highlight").map(parse_line_ranges).unwrap_or_default();
+
+        let (lines, highlighted) = self.code_lines(content, &language, callouts);
+        let class = if highlighted { "syntect highlight" } else { "highlight" };
+        let code = format_lines(&lines, linenums, &ranges);
+
+        self.open_block("listingblock", metadata);
+        self.block_title(metadata);
+        self.out.push_str(&format!("<div class=\"content\">\n<pre class=\"{class}\">"));
+        if language.is_empty() {
+            self.out.push_str("<code>");
+        } else {
+            let language = escape(&language);
+            self.out.push_str(&format!("<code class=\"language-{language}\" data-lang=\"{language}\">"));
+        }
+        self.out.push_str(&code);
+        self.out.push_str("</code></pre>\n</div>\n</div>\n");
+    }
+
+    /// 包含其他块的块
+    fn compound(&mut self, class: &str, metadata: &BlockMetadata, blocks: &[Block]) {
+        self.open_block(class, metadata);
+        self.block_title(metadata);
+        self.out.push_str("<div class=\"content\">\n");
+        self.blocks(blocks);
+        self.out.push_str("</div>\n</div>\n");
+    }
+
+    fn section(&mut self, section: &Section, metadata: &BlockMetadata) {
+        let level = section.level - 1;
+        // 章节的 id 在标题上
+        let id = section.id.as_ref().map(|id| format!(" id=\"{}\"", escape(id))).unwrap_or_default();
+        self.open_block(&format!("sect{level}"), &BlockMetadata { id: None, ..metadata.clone() });
+        self.out.push_str(&format!("<h{}{id}>", level + 1));
+        self.text(section.title);
+        self.out.push_str(&format!("</h{}>\n", level + 1));
+
+        if level == 1 {
+            self.out.push_str("<div class=\"sectionbody\">\n");
+            self.blocks(&section.blocks);
+            self.out.push_str("</div>\n");
+        } else {
+            self.blocks(&section.blocks);
+        }
+        self.out.push_str("</div>\n");
+    }
+
+    /// 替换属性引用，`warn` 模式下的警告指向 `text` 所在的行
+    fn substitute(&mut self, text: &str) -> String {
+        let substitution = self.attrs.substitute(text);
+        for message in substitution.warnings {
+            self.warnings.push(Diagnostic::warning(message, locate(self.source, text)));
+        }
+        substitution.text.into_owned()
+    }
+
+    /// 按行内格式输出
+    fn text(&mut self, text: &str) {
+        self.inlines(&parse_inlines(Span::new(text)));
+    }
+
+    /// 与 Asciidoctor 的替换顺序一致，先识别格式再替换属性引用，
+    /// 因此属性值中的 `*` 等字符不会被当作格式
+    fn inlines(&mut self, inlines: &[Inline]) {
+        for inline in inlines {
+            match &inline.kind {
+                InlineKind::Text(text) => {
+                    let text = self.substitute(text);
+                    self.out.push_str(&escape(&text));
+                }
+                InlineKind::Formatted(text) => self.formatted_text(text),
+                InlineKind::Xref(xref) => self.xref(xref),
+            }
+        }
+    }
+
+    /// 没有文字的引用使用目标的标题，目标没有标题时显示为 `[id]`
+    fn xref(&mut self, xref: &Xref) {
+        let href = match (xref.path, xref.id) {
+            (Some(path), id) => {
+                let path = path.strip_suffix(".adoc").map_or(path.to_string(), |path| format!("{path}.html"));
+                id.map_or(path.clone(), |id| format!("{path}#{id}"))
+            }
+            (None, id) => format!("#{}", id.unwrap_or("")),
+        };
+        // 标题中的交叉引用可能指向标题本身，不再展开
+        let title = match xref.path {
+            None if !self.in_xref => xref.id.and_then(|id| self.references.get(id).and_then(Option::as_deref)),
+            _ => None,
+        };
+
+        let in_xref = self.in_xref;
+        if !in_xref {
+            self.out.push_str(&format!("<a href=\"{}\">", escape(&href)));
+        }
+        self.in_xref = true;
+        match (xref.text.as_deref(), title) {
+            (Some(text), _) => self.text(text),
+            (None, Some(title)) => self.text(title),
+            (None, None) if xref.path.is_some() => self.out.push_str(&escape(&href)),
+            (None, None) => self.out.push_str(&format!("[{}]", escape(xref.id.unwrap_or("")))),
+        }
+        self.in_xref = in_xref;
+        if !in_xref {
+            self.out.push_str("</a>");
+        }
+    }
+
+    fn formatted_text(&mut self, text: &FormattedText) {
+        let (tag, children) = match text {
+            FormattedText::Strong(children) => ("strong", children),
+            FormattedText::Emphasis(children) => ("em", children),
+            FormattedText::Monospace(children) => ("code", children),
+            FormattedText::Highlight(children) => ("mark", children),
+            FormattedText::Superscript(children) => ("sup", children),
+            FormattedText::Subscript(children) => ("sub", children),
+        };
+        self.out.push_str(&format!("<{tag}>"));
+        self.inlines(children);
+        self.out.push_str(&format!("</{tag}>"));
+    }
+}
+
+/// 转义 HTML 特殊字符
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::Attribute;
+    use crate::converter::html::convert;
+    use crate::converter::Options;
+    use crate::parser::parse_document;
+    use crate::safe_mode::SafeMode;
+
+    #[test]
+    pub fn test_convert_embedded() {
+        let doc = parse_document("= Doc\n\nIntro *bold*.\n\n== One\n\nA & B.\n\n=== Sub\n\n_Deep_ text.\n").unwrap();
+        assert_eq!(
+            convert(&doc, &Options { embedded: true, ..Options::default() }).content,
+            "<div id=\"preamble\">\n<div class=\"sectionbody\">\n\
+             <div class=\"paragraph\">\n<p>Intro <strong>bold</strong>.</p>\n</div>\n\
+             </div>\n</div>\n\
+             <div class=\"sect1\">\n<h2 id=\"_one\">One</h2>\n<div class=\"sectionbody\">\n\
+             <div class=\"paragraph\">\n<p>A &amp; B.</p>\n</div>\n\
+             <div class=\"sect2\">\n<h3 id=\"_sub\">Sub</h3>\n\
+             <div class=\"paragraph\">\n<p><em>Deep</em> text.</p>\n</div>\n\
+             </div>\n\
+             </div>\n</div>\n"
+        );
+    }
+
+    #[test]
+    pub fn test_convert_document() {
+        let doc = parse_document(include_str!("../../readme.adoc")).unwrap();
+        let html = convert(&doc, &Options::default()).content;
+
+        assert!(html.starts_with("<!DOCTYPE html>\n"));
+        assert!(html.contains("<title>Rusciidoc</title>\n"));
+        assert!(html.contains("<meta name=\"author\" content=\"Heng Yue Wang\">\n"));
+        assert!(html.contains("<div id=\"header\">\n<h1>Rusciidoc</h1>\n"));
+        assert!(html.contains("<a href=\"mailto:admin@eastack.me\">admin@eastack.me</a>"));
+        assert!(html.contains(
+            "<div id=\"content\">\n<div class=\"paragraph\">\n<p>The AsciiDoc implementation for Rust.</p>\n</div>\n</div>\n"
+        ));
+    }
+
+    #[test]
+    pub fn test_convert_attribute_override() {
+        let doc = parse_document("= Doc\n:lang: fr\n").unwrap();
+        assert!(convert(&doc, &Options::default()).content.starts_with("<!DOCTYPE html>\n<html lang=\"fr\">"));
+
+        let options = Options {
+            attributes: vec![Attribute::from_override("lang=de")],
+            ..Options::default()
+        };
+        assert!(convert(&doc, &options).content.starts_with("<!DOCTYPE html>\n<html lang=\"de\">"));
+    }
+
+    #[test]
+    pub fn test_convert_revision() {
+        let doc = parse_document("= Doc\nAuthor Name\nv1.0, 2024-01-01: Draft\n").unwrap();
+        assert!(convert(&doc, &Options::default()).content.contains(
+            "<span id=\"revnumber\">version 1.0,</span>\n\
+             <span id=\"revdate\">2024-01-01</span>\n\
+             <br><span id=\"revremark\">Draft</span>\n"
+        ));
+    }
+
+    #[test]
+    pub fn test_convert_multiple_authors() {
+        let doc = parse_document("= Doc\nA One <a@x>; B Two\n").unwrap();
+        let html = convert(&doc, &Options::default()).content;
+        assert!(html.contains("<meta name=\"author\" content=\"A One, B Two\">\n"));
+        assert!(html.contains(
+            "<span id=\"author\" class=\"author\">A One</span><br>\n\
+             <span id=\"email\" class=\"email\"><a href=\"mailto:a@x\">a@x</a></span><br>\n\
+             <span id=\"author2\" class=\"author\">B Two</span><br>\n"
+        ));
+    }
+
+    #[test]
+    pub fn test_convert_attribute_references() {
+        let doc = parse_document(
+            "= {product} Guide\n:product: Rusciidoc\n:attribute-missing: warn\n\n== About {product}\n\nUse {product} {version}.\n",
+        )
+        .unwrap();
+        let output = convert(&doc, &Options::default());
+
+        assert!(output.content.contains("<title>Rusciidoc Guide</title>"));
+        assert!(output.content.contains("<h2 id=\"_about_rusciidoc\">About Rusciidoc</h2>"));
+        assert!(output.content.contains("<p>Use Rusciidoc {version}.</p>"));
+
+        let doc = parse_document("= Doc\n:product: Rusciidoc\n\n[[about]]\n== About {product}\n\nSee <<about>>.\n").unwrap();
+        let html = convert(&doc, &Options { embedded: true, ..Options::default() }).content;
+        assert!(html.contains("<p>See <a href=\"#about\">About Rusciidoc</a>.</p>"));
+        assert_eq!(output.warnings.len(), 1);
+        assert_eq!(output.warnings[0].location.start.line, 7);
+    }
+
+    #[test]
+    pub fn test_convert_attribute_entries_in_order() {
+        let doc = parse_document(
+            "= Doc\n:x: one\n:attribute-missing: warn\n\n{x} a.\n\n:x: two\n\n{x} b.\n\n:!x:\n\n{x} c.\n\n:y: {x}\n",
+        )
+        .unwrap();
+        let output = convert(&doc, &Options { embedded: true, ..Options::default() });
+        assert!(output.content.contains("<p>one a.</p>"));
+        assert!(output.content.contains("<p>two b.</p>"));
+        assert!(output.content.contains("<p>{x} c.</p>"));
+
+        let warnings: Vec<_> = output
+            .warnings
+            .iter()
+            .map(|warning| (warning.message.as_str(), warning.location.start.line))
+            .collect();
+        assert_eq!(
+            warnings,
+            [
+                ("skipping reference to missing attribute: x", 13),
+                ("skipping reference to missing attribute: x", 15),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_convert_attribute_substitution_order() {
+        // 属性值在识别格式之后替换，其中的格式标记原样输出
+        let doc = parse_document(":pattern: *.rs\n:bold: *text*\n\n{pattern} and {bold} are *{bold}*.\n\n* {pattern} *x*\n").unwrap();
+        let html = convert(&doc, &Options { embedded: true, ..Options::default() }).content;
+        assert!(html.contains("<p>*.rs and *text* are <strong>*text*</strong>.</p>"));
+        assert!(html.contains("<p>*.rs <strong>x</strong></p>"));
+    }
+
+    #[test]
+    pub fn test_convert_character_replacement_attributes() {
+        let doc = parse_document("A{nbsp}B {apos} {quot} {plus} {zwsp}{brvbar} {amp} {lt}\n").unwrap();
+        assert_eq!(
+            convert(&doc, &Options { embedded: true, ..Options::default() }).content,
+            "<div class=\"paragraph\">\n<p>A\u{a0}B ' &quot; + \u{200b}¦ &amp; &lt;</p>\n</div>\n"
+        );
+    }
+
+    #[test]
+    pub fn test_convert_delimited_blocks() {
+        let doc = parse_document("----\n<tag> & *raw*\n----\n\n++++\n<b>pass</b>\n++++\n\n____\nQuoted.\n____\n").unwrap();
+        assert_eq!(
+            convert(&doc, &Options { embedded: true, ..Options::default() }).content,
+            "<div class=\"listingblock\">\n<div class=\"content\">\n<pre>&lt;tag&gt; &amp; *raw*</pre>\n</div>\n</div>\n\
+             <b>pass</b>\n\
+             <div class=\"quoteblock\">\n<blockquote>\n\
+             <div class=\"paragraph\">\n<p>Quoted.</p>\n</div>\n\
+             </blockquote>\n</div>\n"
+        );
+    }
+
+    #[test]
+    pub fn test_convert_block_metadata() {
+        let doc = parse_document(
+            "[#intro.lead]\n.Title\nText.\n\n[quote, Abraham Lincoln, Address]\n____\nFour score.\n____\n",
+        )
+        .unwrap();
+        assert_eq!(
+            convert(&doc, &Options { embedded: true, ..Options::default() }).content,
+            "<div id=\"intro\" class=\"paragraph lead\">\n<div class=\"title\">Title</div>\n<p>Text.</p>\n</div>\n\
+             <div class=\"quoteblock\">\n<blockquote>\n\
+             <div class=\"paragraph\">\n<p>Four score.</p>\n</div>\n\
+             </blockquote>\n\
+             <div class=\"attribution\">\n&#8212; Abraham Lincoln<br>\n<cite>Address</cite>\n</div>\n\
+             </div>\n"
+        );
+    }
+
+    #[test]
+    pub fn test_convert_source_block() {
+        let doc = parse_document("[source,rust,linenums,highlight=2]\n----\nfn main() {\n}\n----\n").unwrap();
+        let html = convert(&doc, &Options { embedded: true, ..Options::default() }).content;
+        assert!(html.starts_with("<div class=\"listingblock\">\n<div class=\"content\">\n<pre class=\""));
+        assert!(html.contains("<code class=\"language-rust\" data-lang=\"rust\"><span class=\"linenos\">1</span>"));
+        assert!(html.contains("<span class=\"hll\"><span class=\"linenos\">2</span>"));
+        assert!(html.ends_with("</code></pre>\n</div>\n</div>\n"));
+
+        let doc = parse_document(":source-language: text\n\n[source]\n----\na < b\n----\n").unwrap();
+        let html = convert(&doc, &Options { embedded: true, ..Options::default() }).content;
+        assert!(html.contains("data-lang=\"text\">a &lt; b</code>"));
+    }
+
+    #[test]
+    pub fn test_convert_callouts() {
+        let doc = parse_document("----\nlet x = 1; // <1>\nputs(x) <2>\n----\n<1> Define *x*.\n<3> Missing.\n").unwrap();
+        let output = convert(&doc, &Options { embedded: true, ..Options::default() });
+        assert_eq!(
+            output.content,
+            "<div class=\"listingblock\">\n<div class=\"content\">\n<pre>\
+             let x = 1; <a href=\"#CO1-1\"><b class=\"conum\">(1)</b></a>\n\
+             puts(x) <a href=\"#CO1-2\"><b class=\"conum\">(2)</b></a></pre>\n</div>\n</div>\n\
+             <div class=\"colist arabic\">\n<ol>\n\
+             <li id=\"CO1-1\">\n<p>Define <strong>x</strong>.</p>\n</li>\n\
+             <li id=\"CO1-3\">\n<p>Missing.</p>\n</li>\n\
+             </ol>\n</div>\n"
+        );
+
+        let messages: Vec<String> = output.warnings.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "3:9: warning: no callout list item found for <2>",
+                "6:1: warning: no callout found for <3>",
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_convert_lists() {
+        let doc = parse_document(
+            "* [x] Done\n* [ ] Todo\n\n[%reversed,start=3]\n. Three\n.. Nested\n+\n----\ncode\n----\n. Four\n",
+        )
+        .unwrap();
+        assert_eq!(
+            convert(&doc, &Options { embedded: true, ..Options::default() }).content,
+            "<div class=\"ulist checklist\">\n<ul class=\"checklist\">\n\
+             <li>\n<p>&#10003; Done</p>\n</li>\n\
+             <li>\n<p>&#10063; Todo</p>\n</li>\n\
+             </ul>\n</div>\n\
+             <div class=\"olist arabic\">\n<ol class=\"arabic\" start=\"3\" reversed>\n\
+             <li>\n<p>Three</p>\n\
+             <div class=\"olist loweralpha\">\n<ol class=\"loweralpha\" type=\"a\">\n\
+             <li>\n<p>Nested</p>\n\
+             <div class=\"listingblock\">\n<div class=\"content\">\n<pre>code</pre>\n</div>\n</div>\n\
+             </li>\n\
+             </ol>\n</div>\n\
+             </li>\n\
+             <li>\n<p>Four</p>\n</li>\n\
+             </ol>\n</div>\n"
+        );
+    }
+
+    #[test]
+    pub fn test_convert_description_lists() {
+        let doc = parse_document("CPU:: The brain.\nRAM::\n* fast\n\n[qanda]\nWhy?:: Because.\n\n[horizontal]\nKey;; Value\n").unwrap();
+        assert_eq!(
+            convert(&doc, &Options { embedded: true, ..Options::default() }).content,
+            "<div class=\"dlist\">\n<dl>\n\
+             <dt class=\"hdlist1\">CPU</dt>\n<dd>\n<p>The brain.</p>\n</dd>\n\
+             <dt class=\"hdlist1\">RAM</dt>\n<dd>\n\
+             <div class=\"ulist\">\n<ul>\n<li>\n<p>fast</p>\n</li>\n</ul>\n</div>\n\
+             </dd>\n\
+             </dl>\n</div>\n\
+             <div class=\"qlist qanda\">\n<ol>\n<li>\n<p><em>Why?</em></p>\n<p>Because.</p>\n</li>\n</ol>\n</div>\n\
+             <div class=\"hdlist\">\n<table>\n\
+             <tr>\n<td class=\"hdlist1\">\nKey\n</td>\n<td class=\"hdlist2\">\n<p>Value</p>\n</td>\n</tr>\n\
+             </table>\n</div>\n"
+        );
+    }
+
+    #[test]
+    pub fn test_convert_table() {
+        let doc = parse_document(
+            ".Prices\n[cols=\"2,>1m\"]\n|===\n|Item |Price\n\n|Tea |1.00\n2+a|\n* a list\n|===\n",
+        )
+        .unwrap();
+        assert_eq!(
+            convert(&doc, &Options { embedded: true, ..Options::default() }).content,
+            "<table class=\"tableblock frame-all grid-all stretch\">\n\
+             <caption class=\"title\">Table 1. Prices</caption>\n\
+             <colgroup>\n<col style=\"width: 66.6667%;\">\n<col style=\"width: 33.3333%;\">\n</colgroup>\n\
+             <thead>\n<tr>\n\
+             <th class=\"tableblock halign-left valign-top\">Item</th>\n\
+             <th class=\"tableblock halign-right valign-top\">Price</th>\n\
+             </tr>\n</thead>\n\
+             <tbody>\n<tr>\n\
+             <td class=\"tableblock halign-left valign-top\"><p class=\"tableblock\">Tea</p></td>\n\
+             <td class=\"tableblock halign-right valign-top\"><p class=\"tableblock\"><code>1.00</code></p></td>\n\
+             </tr>\n<tr>\n\
+             <td class=\"tableblock halign-left valign-top\" colspan=\"2\"><div class=\"content\">\n\
+             <div class=\"ulist\">\n<ul>\n<li>\n<p>a list</p>\n</li>\n</ul>\n</div>\n\
+             </div></td>\n\
+             </tr>\n</tbody>\n\
+             </table>\n"
+        );
+    }
+
+    #[test]
+    pub fn test_convert_admonitions() {
+        let doc = parse_document(":tip-caption: Hint\n\nNOTE: Read *this*.\n\n.Careful\n[TIP]\n====\nInside.\n====\n").unwrap();
+        assert_eq!(
+            convert(&doc, &Options { embedded: true, ..Options::default() }).content,
+            "<div class=\"admonitionblock note\">\n<table>\n<tr>\n\
+             <td class=\"icon\">\n<div class=\"title\">Note</div>\n</td>\n\
+             <td class=\"content\">\nRead <strong>this</strong>.\n</td>\n\
+             </tr>\n</table>\n</div>\n\
+             <div class=\"admonitionblock tip\">\n<table>\n<tr>\n\
+             <td class=\"icon\">\n<div class=\"title\">Hint</div>\n</td>\n\
+             <td class=\"content\">\n<div class=\"title\">Careful</div>\n\
+             <div class=\"paragraph\">\n<p>Inside.</p>\n</div>\n</td>\n\
+             </tr>\n</table>\n</div>\n"
+        );
+    }
+
+    #[test]
+    pub fn test_convert_safe_mode() {
+        let doc = parse_document(":docdir: /srv/docs\n\n{docdir}\n\n++++\n<script>x</script>\n++++\n").unwrap();
+        let output = convert(&doc, &Options { embedded: true, ..Options::default() });
+        assert_eq!(
+            output.content,
+            "<div class=\"paragraph\">\n<p>/srv/docs</p>\n</div>\n<script>x</script>\n"
+        );
+        assert!(output.warnings.is_empty());
+
+        let options = Options {
+            embedded: true,
+            safe_mode: SafeMode::Server,
+            ..Options::default()
+        };
+        let output = convert(&doc, &options);
+        assert_eq!(
+            output.content,
+            "<div class=\"paragraph\">\n<p></p>\n</div>\n\
+             <div class=\"literalblock\">\n<div class=\"content\">\n<pre>&lt;script&gt;x&lt;/script&gt;</pre>\n</div>\n</div>\n"
+        );
+        let warnings: Vec<_> = output.warnings.iter().map(|warning| warning.message.as_str()).collect();
+        assert_eq!(
+            warnings,
+            ["attribute docdir is locked in server mode", "passthrough block is escaped in server mode"]
+        );
+        assert_eq!(output.warnings[1].location.start.line, 5);
+    }
+
+    #[test]
+    pub fn test_convert_xrefs() {
+        let doc = parse_document(
+            "== *Quick* Start\n\n\
+             See <<_quick_start>>, <<_quick_start,here>>, <<anchor>>, <<nowhere>> and xref:guide.adoc#setup[the guide].\n\n\
+             [[anchor]]\nAnchored.\n",
+        )
+        .unwrap();
+        assert_eq!(
+            convert(&doc, &Options { embedded: true, ..Options::default() }).content,
+            "<div class=\"sect1\">\n<h2 id=\"_quick_start\"><strong>Quick</strong> Start</h2>\n\
+             <div class=\"sectionbody\">\n<div class=\"paragraph\">\n\
+             <p>See <a href=\"#_quick_start\"><strong>Quick</strong> Start</a>, <a href=\"#_quick_start\">here</a>, \
+             <a href=\"#anchor\">[anchor]</a>, <a href=\"#nowhere\">[nowhere]</a> and \
+             <a href=\"guide.html#setup\">the guide</a>.</p>\n</div>\n\
+             <div id=\"anchor\" class=\"paragraph\">\n<p>Anchored.</p>\n</div>\n</div>\n</div>\n"
+        );
+        assert_eq!(doc.warnings[0].message, "possible invalid reference: nowhere");
+
+        // 标题中指向自身的引用不会无限展开
+        let doc = parse_document("[[loop]]\n== See <<loop>>\n\n<<loop>>\n").unwrap();
+        let html = convert(&doc, &Options { embedded: true, ..Options::default() }).content;
+        assert!(html.contains("<p><a href=\"#loop\">See [loop]</a></p>"));
+
+        // 单元格和块标题中的引用同样使用目标的标题
+        let doc = parse_document("[[t]]\n.Target\n====\nx\n====\n\n.See <<t>>\n|===\n|<<t>>\n|===\n").unwrap();
+        let html = convert(&doc, &Options { embedded: true, ..Options::default() }).content;
+        assert!(html.contains("<caption class=\"title\">Table 1. See <a href=\"#t\">Target</a></caption>"));
+        assert!(html.contains("<p class=\"tableblock\"><a href=\"#t\">Target</a></p>"));
+        assert_eq!(doc.warnings, vec![]);
+    }
+}
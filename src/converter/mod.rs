@@ -0,0 +1,50 @@
+use std::str::FromStr;
+
+use crate::ast::{Attribute, Document};
+use crate::diagnostic::Diagnostic;
+use crate::safe_mode::SafeMode;
+
+pub mod highlight;
+pub mod html;
+
+/// 输出格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Backend {
+    Html5,
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "html" | "html5" => Ok(Backend::Html5),
+            _ => Err(format!("unknown backend: {s}")),
+        }
+    }
+}
+
+/// 转换选项
+#[derive(Debug, Default)]
+pub struct Options<'a> {
+    /// 覆盖文档属性，例如命令行中的 `-a name=value`
+    pub attributes: Vec<Attribute<'a>>,
+    /// 只生成文档主体，不包含页头和页脚
+    pub embedded: bool,
+    /// `server` 及以上的模式中直通块按原样文本转义输出，`docdir` 等属性被锁定
+    pub safe_mode: SafeMode,
+}
+
+/// 转换结果
+#[derive(Debug)]
+pub struct Output {
+    pub content: String,
+    pub warnings: Vec<Diagnostic>,
+}
+
+/// 使用指定的后端转换文档
+pub fn convert(doc: &Document, backend: Backend, options: &Options) -> Output {
+    match backend {
+        Backend::Html5 => html::convert(doc, options),
+    }
+}
@@ -0,0 +1,152 @@
+//! 解析和转换过程中产生的诊断信息
+
+use std::fmt;
+
+use crate::ast::Location;
+
+/// 诊断的严重程度
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Warning,
+    Error,
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Severity::Warning => f.write_str("warning"),
+            Severity::Error => f.write_str("error"),
+        }
+    }
+}
+
+/// 一条指向源文件位置的诊断
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    pub location: Location,
+    /// 附加说明，例如出错时所在的外层结构
+    pub notes: Vec<String>,
+    /// 位置所在的被包含文件的规范名称，为 `None` 时位于顶层文档
+    pub file: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn error(message: impl Into<String>, location: Location) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            message: message.into(),
+            location,
+            notes: vec![],
+            file: None,
+        }
+    }
+
+    pub fn warning(message: impl Into<String>, location: Location) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            message: message.into(),
+            location,
+            notes: vec![],
+            file: None,
+        }
+    }
+
+    /// 以 rustc 的格式输出，包含出错的源码行和指向出错位置的 `^`
+    ///
+    /// ```text
+    /// warning: unterminated comment block
+    ///  --> readme.adoc:3:1
+    ///   |
+    /// 3 | ////
+    ///   | ^^^^
+    ///   |
+    ///   = note: ...
+    /// ```
+    pub fn render(&self, source: &str, path: &str) -> String {
+        let start = self.location.start;
+        let gutter = " ".repeat(start.line.to_string().len());
+        let text = source
+            .lines()
+            .nth(start.line.saturating_sub(1))
+            .unwrap_or("")
+            .trim_end_matches('\r');
+
+        // 跨行的范围只标记到起始行的末尾
+        let end_column = if self.location.end.line == start.line {
+            self.location.end.column
+        } else {
+            text.chars().count() + 1
+        };
+        let width = end_column.saturating_sub(start.column).max(1);
+
+        let mut out = format!("{}: {}\n", self.severity, self.message);
+        out.push_str(&format!("{gutter}--> {path}:{}:{}\n", start.line, start.column));
+        out.push_str(&format!("{gutter} |\n"));
+        out.push_str(&format!("{} | {text}\n", start.line));
+        out.push_str(&format!(
+            "{gutter} | {}{}\n",
+            " ".repeat(start.column.saturating_sub(1)),
+            "^".repeat(width),
+        ));
+        if !self.notes.is_empty() {
+            out.push_str(&format!("{gutter} |\n"));
+            for note in &self.notes {
+                out.push_str(&format!("{gutter} = note: {note}\n"));
+            }
+        }
+        out
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let start = self.location.start;
+        write!(f, "{}:{}: {}: {}", start.line, start.column, self.severity, self.message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{Location, Position};
+    use crate::diagnostic::Diagnostic;
+
+    #[test]
+    pub fn test_render() {
+        let source = "= Title\n\n////\nhidden\n";
+        let mut diagnostic = Diagnostic::error(
+            "expected closing `////` for comment block opened at line 3",
+            Location {
+                start: Position { line: 3, column: 1 },
+                end: Position { line: 3, column: 5 },
+            },
+        );
+        diagnostic.notes.push("comment blocks end with a line containing only `////`".to_string());
+
+        assert_eq!(
+            diagnostic.render(source, "doc.adoc"),
+            "error: expected closing `////` for comment block opened at line 3\n\
+             \x20--> doc.adoc:3:1\n\
+             \x20 |\n\
+             3 | ////\n\
+             \x20 | ^^^^\n\
+             \x20 |\n\
+             \x20 = note: comment blocks end with a line containing only `////`\n"
+        );
+    }
+
+    #[test]
+    pub fn test_render_multiline() {
+        let source = "first line\nsecond line\n";
+        let diagnostic = Diagnostic::warning(
+            "something",
+            Location {
+                start: Position { line: 1, column: 7 },
+                end: Position { line: 2, column: 3 },
+            },
+        );
+        assert!(diagnostic.render(source, "-").contains("1 | first line\n  |       ^^^^\n"));
+        assert_eq!(diagnostic.to_string(), "1:7: warning: something");
+    }
+}
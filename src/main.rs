@@ -1,338 +1,243 @@
-use nom::{
-    bytes::complete::{is_not, take_until, take_while1},
-    character::{
-        complete::{char, space0, space1},
-    },
-    combinator::opt,
-    IResult,
-    multi::many0,
-    sequence::{delimited, pair, preceded, terminated, tuple},
-};
-use nom::bytes::complete::{is_a, take_till, take_while};
-use nom::character::{is_alphabetic, is_space};
-use nom::character::complete::{anychar, line_ending};
-use nom::combinator::{cut, not};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::{env, fs, io, process};
 
-#[derive(Debug)]
-pub struct Document<'a> {
-    pub header: Header<'a>,
-    pub blocks: Section<'a>,
-    pub attrs: Vec<Attribute<'a>>,
-}
+use rusciidoc::converter::{self, Backend, Options};
+use rusciidoc::preprocessor::{self, FileResolver, Output, Resolved};
+use rusciidoc::{load_document, Attribute, Diagnostic, SafeMode, Severity};
 
-#[derive(Debug)]
-pub struct Header<'a> {
-    pub title: &'a str,
-    pub auth_info: Option<AuthorInfo<'a>>,
-    pub attrs: Vec<Attribute<'a>>,
-}
+const USAGE: &str = "\
+Usage: rusciidoc [OPTIONS] [FILE]
 
-#[derive(Debug)]
-pub struct Attribute<'a> {
-    pub unset: bool,
-    pub name: &'a str,
-    pub value: Option<&'a str>,
-}
+Converts the AsciiDoc FILE to HTML. Reads from stdin when FILE is `-` or omitted.
 
-#[derive(Debug, Default, PartialEq)]
-pub struct AuthorInfo<'a> {
-    pub author: Name<'a>,
-    pub email: Option<&'a str>,
-}
+Options:
+  -o, --out-file FILE     output file, `-` for stdout (default: FILE with .html extension)
+  -b, --backend BACKEND   output backend: html5 (default: html5)
+  -a, --attribute ATTR    set a document attribute: name=value, name, or name! to unset
+  -s, --embedded          output the document body only, without header and footer
+  -S, --safe-mode MODE    safe mode: unsafe, safe, server or secure (default: unsafe)
+  -h, --help              print this help
+";
 
 #[derive(Debug, Default, PartialEq)]
-pub struct Name<'a> {
-    pub firstname: &'a str,
-    pub middle_name: Option<&'a str>,
-    pub lastname: Option<&'a str>,
-}
-
-#[derive(Debug, Default)]
-pub struct Section<'a> {
-    pub title: &'a str,
-    pub blocks: &'a Vec<Block<'a>>,
-}
-
-#[derive(Debug, Default)]
-pub struct Block<'a> {
-    pub lines: &'a str,
+struct Cli {
+    input: Option<PathBuf>,
+    out_file: Option<PathBuf>,
+    backend: Option<String>,
+    attributes: Vec<String>,
+    embedded: bool,
+    safe_mode: Option<String>,
+    help: bool,
 }
 
-//pub fn single_revnumber(input: &str) -> IResult<&str, &str> {
-//    delimited(
-//        char('v'),
-//        take_while1(|c: char| c.is_numeric() || c == '.'),
-//        pair(space0, ),
-//    )
-//}
-//
-//pub fn parse_revnumber(input: &str) -> IResult<&str, &str> {
-//    delimited(
-//        space0,
-//        take_while1(|c: char| c.is_numeric() || c == '.'),
-//        space0,
-//    )
-//}
-//
-//pub fn parse_revdata(input: &str) -> IResult<&str, &str> {
-//    delimited(
-//        space0,
-//        take_while1(|c: char| c.is_numeric() || c == '-'),
-//        space0,
-//    )
-//}
-//
-//pub fn parse_revremark(input: &str) -> IResult<&str, &str> {
-//    preceded(pair(char(':'), space0), (is_not('\n'), char('\n')))
-//}
-//
-//pub fn parse_revision(input: &str) -> IResult<&str, &str> {
-//    alt((
-//        preceded(char('v'), parse_revnumber),
-//        tuple((parse_revnumber, char(','), parse_revdata)),
-//        tuple((parse_revnumber, char(','), parse_revdata, parse_revremark)),
-//    ));
-//}
-
-pub fn name(input: &str) -> IResult<&str, &str> {
-    // is_not(line_ending)(input)
-    // not(line_ending::<&str, &str>)
-    is_not("\n\t ")(input)
-}
-
-/// 解析文档头部的作者信息
-pub fn parse_author_line(i: &str) -> IResult<&str, AuthorInfo> {
-    let auth = tuple((
-        terminated(name, space0),
-        opt(terminated(name, space0)),
-        opt(terminated(name, space0)),
-    ));
-    let email = terminated(delimited(char('<'), is_not(">"), char('>')), space0);
-
-    let (i, ((firstname, middlename, lastname), email)) =
-        terminated(pair(auth, opt(email)), line_ending)(i)?;
+/// 解析命令行参数，支持 `-o FILE`、`--out-file FILE` 和 `--out-file=FILE` 等写法
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Cli, String> {
+    let mut cli = Cli::default();
+    let mut args = args.into_iter();
+
+    while let Some(arg) = args.next() {
+        let (flag, inline_value) = match arg.split_once('=') {
+            Some((flag, value)) if arg.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+            _ => (arg.clone(), None),
+        };
+
+        let mut value = |name: &str| {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or_else(|| format!("option {name} requires a value"))
+        };
+
+        match flag.as_str() {
+            "-o" | "--out-file" => cli.out_file = Some(PathBuf::from(value(&flag)?)),
+            "-b" | "--backend" => cli.backend = Some(value(&flag)?),
+            "-a" | "--attribute" => cli.attributes.push(value(&flag)?),
+            "-s" | "--embedded" => cli.embedded = true,
+            "-S" | "--safe-mode" => cli.safe_mode = Some(value(&flag)?),
+            "-h" | "--help" => cli.help = true,
+            "-" => cli.input = None,
+            _ if flag.starts_with('-') => return Err(format!("unknown option: {flag}")),
+            _ if cli.input.is_some() => return Err(format!("unexpected argument: {arg}")),
+            _ => cli.input = Some(PathBuf::from(arg)),
+        }
+    }
 
-    Ok((
-        i,
-        AuthorInfo {
-            author: Name {
-                firstname,
-                middle_name: lastname.and(middlename),
-                lastname: lastname.or(middlename),
-            },
-            email,
-        },
-    ))
+    Ok(cli)
 }
 
-pub fn parse_doc_header(i: &str) -> IResult<&str, Header> {
-    let (i, title) = preceded(
-        pair(char('='), space1),
-        terminated(is_not("\r\n"), line_ending),
-    )(i)?;
-
-    let (i, auth_info) = opt(parse_author_line)(i)?;
-    let (i, attrs) = many0(terminated(parse_doc_attr, line_ending))(i)?;
-
-    Ok((
-        i,
-        Header {
-            title,
-            auth_info,
-            attrs,
-        },
-    ))
+/// 未指定输出文件时，文件输入写到同名的 `.html` 文件，标准输入写到标准输出
+fn out_file(cli: &Cli) -> Option<PathBuf> {
+    match (&cli.out_file, &cli.input) {
+        (Some(out_file), _) if out_file == Path::new("-") => None,
+        (Some(out_file), _) => Some(out_file.clone()),
+        (None, Some(input)) => Some(input.with_extension("html")),
+        (None, None) => None,
+    }
 }
 
-pub fn parse_doc_section(i: &str) -> IResult<&str, DocContent> {
-    let line = delimited(line_ending, is_not("\r\n"), line_ending)(i)?;
+/// 按 rustc 的格式输出诊断，每条诊断之后空一行
+///
+/// 位于被包含文件中的诊断显示该文件的名称和其中的行。
+fn report(diagnostics: &[Diagnostic], source: &str, path: &str, files: &[Resolved]) {
+    for diagnostic in diagnostics {
+        let rendered = match &diagnostic.file {
+            Some(file) => {
+                let content = files.iter().find(|resolved| resolved.path == *file);
+                diagnostic.render(content.map_or("", |resolved| resolved.content.as_str()), file)
+            }
+            None => diagnostic.render(source, path),
+        };
+        eprintln!("{rendered}");
+    }
 }
 
-/// 解析文档属性
-pub fn parse_doc_attr(i: &str) -> IResult<&str, Attribute> {
-    let name = delimited(
-        preceded(char(':'), space0),
-        pair(opt(char('!')), take_while1(|c| c != ':')),
-        char(':'),
-    );
-
-    let value = delimited(space1, is_not("\r\n"), line_ending);
-
-    let (i, ((unset, name), value)) = pair(name, opt(value))(i)?;
-
-    Ok((
-        i,
-        Attribute {
-            unset: unset.is_some(),
-            name,
-            value,
-        },
-    ))
+fn run(cli: &Cli) -> Result<(), String> {
+    let backend = match &cli.backend {
+        Some(backend) => backend.parse::<Backend>()?,
+        None => Backend::Html5,
+    };
+    let safe_mode = match &cli.safe_mode {
+        Some(safe_mode) => safe_mode.parse::<SafeMode>()?,
+        None => SafeMode::Unsafe,
+    };
+
+    let input = match &cli.input {
+        Some(path) => fs::read_to_string(path)
+            .map_err(|err| format!("{}: {err}", path.display()))?,
+        None => {
+            let mut input = String::new();
+            io::stdin()
+                .read_to_string(&mut input)
+                .map_err(|err| format!("stdin: {err}"))?;
+            input
+        }
+    };
+
+    let path = cli.input.as_ref().map_or("<stdin>".into(), |path| path.display().to_string());
+    // 文件所在的目录和文件名只作为默认值，`server` 及以上的模式会隐藏它们
+    let mut attributes = cli.attributes.clone();
+    if let Some(input) = &cli.input {
+        let file = fs::canonicalize(input).unwrap_or_else(|_| input.clone());
+        let dir = file.parent().map(|dir| dir.display().to_string()).unwrap_or_default();
+        attributes.push(format!("docdir={dir}@"));
+        attributes.push(format!("docfile={}@", file.display()));
+    }
+    let resolver = FileResolver::default();
+    // 覆盖属性用于条件指令，决定章节 id 的生成方式，也用于替换标题中的属性引用
+    let overrides: Vec<_> = attributes.iter().map(|attr| Attribute::from_override(attr)).collect();
+    let options = preprocessor::Options {
+        resolver: Some(&resolver),
+        path: cli.input.as_ref().map(|_| path.as_str()),
+        attributes: overrides,
+        safe_mode,
+        ..preprocessor::Options::default()
+    };
+    let mut output = Output::default();
+    let (preprocessed, doc) = load_document(&input, &options, &mut output);
+    report(&preprocessed.warnings, &input, &path, &preprocessed.files);
+    let doc = match doc {
+        Ok(doc) => doc,
+        Err(err) => {
+            report(&err.diagnostics, &input, &path, &preprocessed.files);
+            let errors = err
+                .diagnostics
+                .iter()
+                .filter(|diagnostic| diagnostic.severity == Severity::Error)
+                .count();
+            let plural = if errors == 1 { "" } else { "s" };
+            return Err(format!("aborting due to {errors} previous error{plural}"));
+        }
+    };
+    report(&doc.warnings, &input, &path, &preprocessed.files);
+
+    let options = Options {
+        attributes: options.attributes,
+        embedded: cli.embedded,
+        safe_mode,
+    };
+    let output = converter::convert(&doc, backend, &options);
+    // 转换的诊断位置指向展开后的文本，输出之前换算为源文件中的位置
+    let warnings: Vec<_> = output.warnings.iter().map(|warning| preprocessed.remap(warning)).collect();
+    report(&warnings, &input, &path, &preprocessed.files);
+
+    match out_file(cli) {
+        Some(path) => fs::write(&path, output.content).map_err(|err| format!("{}: {err}", path.display())),
+        None => io::stdout()
+            .write_all(output.content.as_bytes())
+            .map_err(|err| format!("stdout: {err}")),
+    }
 }
 
 fn main() {
-    let doc = r#"= Rsciidoc
-Wang  Yue Heng  <admin@eastack.me>
-:hello: world
-:!toc:
-"#;
-    let (_, doc_header) = parse_doc_header(doc).unwrap();
-    println!("Doc header: {doc_header:?}")
+    let cli = parse_args(env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("rusciidoc: {err}\n\n{USAGE}");
+        process::exit(2);
+    });
+
+    if cli.help {
+        print!("{USAGE}");
+        return;
+    }
+
+    if let Err(err) = run(&cli) {
+        eprintln!("rusciidoc: {err}");
+        process::exit(1);
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use nom::Parser;
-    use rusciidoc::test_from_lib;
+    use std::path::PathBuf;
 
     use super::*;
 
-    #[test]
-    pub fn test_parse_attr() {
-        let (_, attrs) = parse_doc_attr(":hello: world\r\n").unwrap();
-        assert_eq!(attrs.name, "hello");
-        assert_eq!(attrs.value, Some("world"));
-    }
-
-    fn assert_parse_auth_line(input: &str, expected: &(&str, AuthorInfo)) -> Result<(), String> {
-        let auth_info = parse_author_line(input).unwrap();
-        if auth_info != *expected {
-            Err("".to_string())
-        } else {
-            Ok(())
-        }
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
     }
 
     #[test]
-    pub fn test_parse_auth_line() {
-        let test_data = [
-            (
-                "Wang\n",
-                (
-                    "",
-                    AuthorInfo {
-                        author: Name {
-                            firstname: "Wang",
-                            middle_name: None,
-                            lastname: None,
-                        },
-                        email: None,
-                    },
-                ),
-            ),
-            (
-                "Wang \n",
-                (
-                    "",
-                    AuthorInfo {
-                        author: Name {
-                            firstname: "Wang",
-                            middle_name: None,
-                            lastname: None,
-                        },
-                        email: None,
-                    },
-                ),
-            ),
-            (
-                "Wang Heng\n",
-                (
-                    "",
-                    AuthorInfo {
-                        author: Name {
-                            firstname: "Wang",
-                            middle_name: None,
-                            lastname: Some("Heng"),
-                        },
-                        email: None,
-                    },
-                ),
-            ),
-            (
-                "Wang Heng \n",
-                (
-                    "",
-                    AuthorInfo {
-                        author: Name {
-                            firstname: "Wang",
-                            middle_name: None,
-                            lastname: Some("Heng"),
-                        },
-                        email: None,
-                    },
-                ),
-            ),
-            (
-                "Wang Yue Heng\n",
-                (
-                    "",
-                    AuthorInfo {
-                        author: Name {
-                            firstname: "Wang",
-                            middle_name: Some("Yue"),
-                            lastname: Some("Heng"),
-                        },
-                        email: None,
-                    },
-                ),
-            ),
-            (
-                "Wang Yue Heng \n",
-                (
-                    "",
-                    AuthorInfo {
-                        author: Name {
-                            firstname: "Wang",
-                            middle_name: Some("Yue"),
-                            lastname: Some("Heng"),
-                        },
-                        email: None,
-                    },
-                ),
-            ),
-            (
-                "Wang Yue Heng <admin@eastack.me>\n",
-                (
-                    "",
-                    AuthorInfo {
-                        author: Name {
-                            firstname: "Wang",
-                            middle_name: Some("Yue"),
-                            lastname: Some("Heng"),
-                        },
-                        email: Some("admin@eastack.me"),
-                    },
-                ),
-            ),
-            (
-                "Wang Yue Heng <admin@eastack.me> \n",
-                (
-                    "",
-                    AuthorInfo {
-                        author: Name {
-                            firstname: "Wang",
-                            middle_name: Some("Yue"),
-                            lastname: Some("Heng"),
-                        },
-                        email: Some("admin@eastack.me"),
-                    },
-                ),
-            ),
-        ];
-
-        test_data
-            .iter()
-            .try_for_each(|(input, expected)| assert_parse_auth_line(input, expected))
-            .unwrap();
+    pub fn test_parse_args() {
+        let cli = parse_args(args(&[
+            "readme.adoc",
+            "-o",
+            "out.html",
+            "-b",
+            "html5",
+            "-a",
+            "toc=left",
+            "--attribute=!lang",
+            "-S",
+            "secure",
+        ]))
+        .unwrap();
+        assert_eq!(
+            cli,
+            Cli {
+                input: Some(PathBuf::from("readme.adoc")),
+                out_file: Some(PathBuf::from("out.html")),
+                backend: Some("html5".to_string()),
+                attributes: vec!["toc=left".to_string(), "!lang".to_string()],
+                embedded: false,
+                safe_mode: Some("secure".to_string()),
+                help: false,
+            }
+        );
+
+        assert!(parse_args(args(&["-o"])).is_err());
+        assert!(parse_args(args(&["--unknown"])).is_err());
+        assert!(parse_args(args(&["a.adoc", "b.adoc"])).is_err());
     }
 
     #[test]
-    fn test() {
-        fn alpha(i: &[u8]) -> IResult<&[u8], &[u8]> {
-            take_while(is_alphabetic)(i)
-        }
+    pub fn test_out_file() {
+        let cli = parse_args(args(&["docs/readme.adoc"])).unwrap();
+        assert_eq!(out_file(&cli), Some(PathBuf::from("docs/readme.html")));
+
+        let cli = parse_args(args(&["docs/readme.adoc", "--out-file", "-"])).unwrap();
+        assert_eq!(out_file(&cli), None);
 
-        let result = alpha(b"hello123");
-        println!("Result: {result:?}");
+        let cli = parse_args(args(&["-"])).unwrap();
+        assert_eq!(out_file(&cli), None);
     }
 }
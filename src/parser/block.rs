@@ -0,0 +1,330 @@
+use nom::bytes::complete::{is_not, tag};
+use nom::character::complete::{char, line_ending, not_line_ending, space0};
+use nom::combinator::{consumed, map, not, opt, value, verify};
+use nom::error::{ErrorKind, ParseError};
+use nom::multi::{many1, many1_count};
+use nom::sequence::{pair, preceded, terminated};
+use nom::Slice;
+
+use crate::ast::{Location, Title};
+use crate::diagnostic::Diagnostic;
+use crate::parser::error::Error;
+use crate::parser::{eol, location, IResult, Span};
+
+pub fn parse_comment_line(i: Span) -> IResult<()> {
+    value(
+        (),
+        pair(tag("//"), is_not("\n\r")),
+    )(i)
+}
+
+/// 分隔块的种类，由分隔行使用的字符决定
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delimiter {
+    Comment,
+    Listing,
+    Literal,
+    Example,
+    Sidebar,
+    Quote,
+    Passthrough,
+    Open,
+    /// `|===`、`,===`、`:===` 或嵌套表格的 `!===`，记录第一个字符
+    Table(char),
+}
+
+impl Delimiter {
+    fn from_char(c: char) -> Option<Self> {
+        match c {
+            '/' => Some(Delimiter::Comment),
+            '-' => Some(Delimiter::Listing),
+            '.' => Some(Delimiter::Literal),
+            '=' => Some(Delimiter::Example),
+            '*' => Some(Delimiter::Sidebar),
+            '_' => Some(Delimiter::Quote),
+            '+' => Some(Delimiter::Passthrough),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Delimiter::Comment => "comment",
+            Delimiter::Listing => "listing",
+            Delimiter::Literal => "literal",
+            Delimiter::Example => "example",
+            Delimiter::Sidebar => "sidebar",
+            Delimiter::Quote => "quote",
+            Delimiter::Passthrough => "passthrough",
+            Delimiter::Open => "open",
+            Delimiter::Table(_) => "table",
+        }
+    }
+}
+
+/// 分隔块的结构，内容由调用者按种类进一步解析
+#[derive(Debug, PartialEq)]
+pub struct DelimitedBlock<'a> {
+    pub delimiter: Delimiter,
+    /// 开始和结束分隔行之间的内容，不包括最后一个换行符
+    pub content: Span<'a>,
+    pub location: Location,
+    /// 没有结束分隔行时的警告
+    pub warning: Option<Diagnostic>,
+}
+
+/// 分隔行：`--`，由四个或更多相同字符组成的行，或者 `|===` 等表格分隔行，行尾可以有空白
+pub fn parse_delimiter_line(i: Span) -> IResult<(Delimiter, Span)> {
+    let (rest, line) = terminated(not_line_ending, eol)(i)?;
+    let text = line.fragment().trim_end();
+    let delimiter = match text.chars().next() {
+        _ if text == "--" => Some(Delimiter::Open),
+        Some(c) if text.len() >= 4 && text.chars().all(|other| other == c) => Delimiter::from_char(c),
+        Some(c @ ('|' | ',' | ':' | '!')) if text.len() >= 4 && text[1..].chars().all(|other| other == '=') => {
+            Some(Delimiter::Table(c))
+        }
+        _ => None,
+    };
+    match delimiter {
+        Some(delimiter) => Ok((rest, (delimiter, line.slice(..text.len())))),
+        None => Err(nom::Err::Error(Error::from_error_kind(i, ErrorKind::Tag))),
+    }
+}
+
+/// 解析分隔块，包括结束分隔行的换行符
+///
+/// 结束分隔行必须与开始分隔行完全相同，因此可以用不同长度的分隔行嵌套同一种块。
+/// 没有结束分隔行时块延续到文档末尾，并带有一条警告。
+pub fn parse_delimited_block(i: Span) -> IResult<DelimitedBlock> {
+    let start = i;
+    let (mut i, (delimiter, open)) = parse_delimiter_line(i)?;
+    let content_start = i;
+
+    let mut warning = None;
+    let content_end = loop {
+        if i.fragment().is_empty() {
+            let message = format!("unterminated {} block", delimiter.name());
+            warning = Some(Diagnostic::warning(message, location(&open)));
+            break i;
+        }
+        let (rest, line) = terminated(not_line_ending, eol)(i)?;
+        if line.fragment().trim_end() == *open.fragment() {
+            let end = i;
+            i = rest;
+            break end;
+        }
+        i = rest;
+    };
+
+    let content_len = content_end.location_offset() - content_start.location_offset();
+    let content = content_start.slice(..content_len);
+    let trimmed = content.fragment().strip_suffix('\n').unwrap_or(content.fragment());
+    let trimmed = trimmed.strip_suffix('\r').unwrap_or(trimmed);
+
+    let len = i.location_offset() - start.location_offset();
+    Ok((
+        i,
+        DelimitedBlock {
+            delimiter,
+            content: content.slice(..trimmed.len()),
+            location: location(&start.slice(..len)),
+            warning,
+        },
+    ))
+}
+
+/// 解析注释块，没有结束分隔行时返回一条警告
+pub fn parse_comment_block(i: Span) -> IResult<Option<Diagnostic>> {
+    map(
+        verify(parse_delimited_block, |block| block.delimiter == Delimiter::Comment),
+        |block| block.warning,
+    )(i)
+}
+
+pub fn parse_title(i: Span<'_>) -> IResult<'_, Title<'_>> {
+    let (i, (span, (level, content))) =
+        consumed(pair(many1_count(char('=')), is_not("\n\r")))(i)?;
+    Ok((
+        i,
+        Title {
+            level,
+            content: content.fragment(),
+            location: location(&span),
+        },
+    ))
+}
+
+/// 连续的非空行，遇到分隔行时结束
+pub fn parse_block(i: Span) -> IResult<Vec<Span>> {
+    terminated(many1(preceded(not(parse_delimiter_line), parse_line)), opt(eol))(i)
+}
+
+pub fn parse_line(i: Span) -> IResult<Span> {
+    terminated(is_not("\r\n"), eol)(i)
+}
+
+pub fn parse_blank_line(i: Span) -> IResult<()> {
+    value((), pair(space0, line_ending))(i)
+}
+
+pub fn parse_section_title(i: Span<'_>) -> IResult<'_, Title<'_>> {
+    terminated(
+        verify(parse_title, |title: &Title| title.content.starts_with(' ')),
+        eol,
+    )(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::multi::many0;
+    use nom::Slice;
+
+    use crate::ast::{Location, Position, Title};
+    use crate::parser::block::{
+        parse_block, parse_comment_block, parse_comment_line, parse_delimited_block, parse_line, parse_title,
+        Delimiter,
+    };
+    use crate::parser::Span;
+
+    #[test]
+    pub fn test_comment_line() {
+        let text = "// I'm comment \n\
+                         I'm not comment.\n\
+                         I'm content.";
+        let (i, r) = parse_comment_line(Span::new(text)).unwrap();
+        assert_eq!(*i.fragment(), "\nI'm not comment.\nI'm content.");
+        assert_eq!(r, ());
+    }
+
+    #[test]
+    pub fn test_comment_block() {
+        let text = "//// \n\
+                         I'm comment \n\
+                         I'm not comment. \n\
+                         I'm content. \n\
+                         ////\n\
+                         I'm not comment.\n\
+                         I'm content.";
+        let (i, r) = parse_comment_block(Span::new(text)).unwrap();
+        assert_eq!(*i.fragment(), "I'm not comment.\nI'm content.");
+        assert_eq!(r, None);
+    }
+
+    #[test]
+    pub fn test_comment_block_delimiters() {
+        // 行中间的 `////` 和长度不同的分隔行都不能结束注释块
+        let text = "//////\n\
+                    a //// b\n\
+                    ////\n\
+                    //////\n\
+                    after";
+        let (i, r) = parse_comment_block(Span::new(text)).unwrap();
+        assert_eq!(*i.fragment(), "after");
+        assert_eq!(r, None);
+
+        assert!(parse_comment_block(Span::new("//// text\n////\n")).is_err());
+    }
+
+    #[test]
+    pub fn test_parse_delimited_block() {
+        let text = "------\n----\ncode\n------\nafter";
+        let (i, block) = parse_delimited_block(Span::new(text)).unwrap();
+        assert_eq!(*i.fragment(), "after");
+        assert_eq!(block.delimiter, Delimiter::Listing);
+        assert_eq!(*block.content.fragment(), "----\ncode");
+        assert_eq!(block.content.location_line(), 2);
+        assert_eq!(
+            block.location,
+            Location {
+                start: Position { line: 1, column: 1 },
+                end: Position { line: 4, column: 7 },
+            }
+        );
+        assert_eq!(block.warning, None);
+
+        let delimiters = [
+            ("....", Delimiter::Literal),
+            ("====", Delimiter::Example),
+            ("****", Delimiter::Sidebar),
+            ("____", Delimiter::Quote),
+            ("++++", Delimiter::Passthrough),
+            ("--", Delimiter::Open),
+            ("|===", Delimiter::Table('|')),
+            (",====", Delimiter::Table(',')),
+        ];
+        for (delimiter, expected) in delimiters {
+            let text = format!("{delimiter}\n\n{delimiter}\n");
+            let (_, block) = parse_delimited_block(Span::new(&text)).unwrap();
+            assert_eq!(block.delimiter, expected, "delimiter: {delimiter}");
+            assert_eq!(*block.content.fragment(), "");
+        }
+
+        assert!(parse_delimited_block(Span::new("---\ntext\n---\n")).is_err());
+        assert!(parse_delimited_block(Span::new("==== Title\n")).is_err());
+    }
+
+    #[test]
+    pub fn test_unterminated_comment_block() {
+        let text = "Text\n////\nhidden\n\n== Hidden\n";
+        let (i, r) = parse_comment_block(Span::new(text).slice(5..)).unwrap();
+        assert_eq!(*i.fragment(), "");
+
+        let warning = r.unwrap();
+        assert_eq!(warning.message, "unterminated comment block");
+        assert_eq!(
+            warning.location,
+            Location {
+                start: Position { line: 2, column: 1 },
+                end: Position { line: 2, column: 5 },
+            }
+        );
+    }
+
+    #[test]
+    pub fn test_parse_title() {
+        let text = "===== Hello Asciidoctor\nHello World!";
+        let (result, title) = parse_title(Span::new(text)).unwrap();
+        assert_eq!(*result.fragment(), "\nHello World!");
+        assert_eq!(
+            title,
+            Title {
+                level: 5,
+                content: " Hello Asciidoctor",
+                location: Location {
+                    start: Position { line: 1, column: 1 },
+                    end: Position { line: 1, column: 24 },
+                },
+            }
+        );
+    }
+
+    #[test]
+    pub fn test_parse_block() {
+        let text = "Asciidoctor is a marklanguage.\n\nIs powerful.\nIs simple.\nIs elegent.\n\nThe End.";
+        let (result, section) = many0(parse_block)(Span::new(text)).unwrap();
+        assert_eq!(*result.fragment(), "");
+        let section: Vec<Vec<&str>> = section
+            .iter()
+            .map(|lines| lines.iter().map(|line| *line.fragment()).collect())
+            .collect();
+        assert_eq!(
+            section,
+            vec![
+                vec!["Asciidoctor is a marklanguage."],
+                vec!["Is powerful.", "Is simple.", "Is elegent."],
+                vec!["The End."],
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_parse_line() {
+        let text = "Asciidoctor is a marklanguage.\n\nIs powerful.\nIs simple.\nIs elegent.\n\nThe End.";
+        let (result, line) = parse_line(Span::new(text)).unwrap();
+        assert_eq!(*line.fragment(), "Asciidoctor is a marklanguage.");
+        assert_eq!(*result.fragment(), "\nIs powerful.\nIs simple.\nIs elegent.\n\nThe End.");
+
+        let (result, line) = parse_line(Span::new("The End.")).unwrap();
+        assert_eq!((*line.fragment(), *result.fragment()), ("The End.", ""));
+    }
+}
@@ -0,0 +1,212 @@
+//! 代码块中的标注和代码块之后的标注列表
+
+use nom::branch::alt;
+use nom::character::complete::{char, digit1, space1};
+use nom::combinator::{consumed, map_opt, not, value};
+use nom::multi::{many0, many1};
+use nom::sequence::{delimited, pair, preceded, terminated};
+use nom::Slice;
+
+use crate::ast::{Block, BlockKind, BlockMetadata, Callout, CalloutItem, Location, Position};
+use crate::parser::block::{parse_blank_line, parse_delimiter_line, parse_line};
+use crate::parser::inline::parse_inlines;
+use crate::parser::{location, IResult, Span};
+
+/// 标注之前可以出现的行注释符号，例如 `// <1>` 和 `# <1>`
+const COMMENT_PREFIXES: [&str; 4] = ["//", "#", "--", ";;"];
+
+/// 拆出一行末尾的标注，返回去掉标注后的代码和标注的编号，`<.>` 的编号为 `None`
+///
+/// 只识别行尾的标注，被 `\` 转义的标注按普通文本处理。
+fn split_line(line: &str) -> (&str, Vec<Option<usize>>) {
+    let mut rest = line.trim_end();
+    let mut markers = vec![];
+    while let Some(before) = rest.strip_suffix('>') {
+        let Some(start) = before.rfind('<') else { break };
+        let marker = match &before[start + 1..] {
+            "." => None,
+            number if !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) => match number.parse() {
+                Ok(number) => Some(number),
+                Err(_) => break,
+            },
+            _ => break,
+        };
+        if before[..start].ends_with('\\') {
+            break;
+        }
+        markers.insert(0, marker);
+        rest = before[..start].trim_end();
+    }
+
+    if markers.is_empty() {
+        return (line, markers);
+    }
+    if let Some(code) = COMMENT_PREFIXES.iter().find_map(|prefix| rest.strip_suffix(prefix)) {
+        rest = code.trim_end();
+    }
+    (rest, markers)
+}
+
+/// 逐行拆出代码块中的标注，`<.>` 按在块中出现的顺序编号
+///
+/// 返回每一行去掉标注后的代码（不含换行符）和该行的标注编号。
+pub fn split_callouts(content: &str) -> Vec<(&str, Vec<usize>)> {
+    let mut auto = 0;
+    content
+        .split('\n')
+        .map(|line| {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            let (code, markers) = split_line(line);
+            let numbers = markers
+                .into_iter()
+                .map(|marker| {
+                    marker.unwrap_or_else(|| {
+                        auto += 1;
+                        auto
+                    })
+                })
+                .collect();
+            (code, numbers)
+        })
+        .collect()
+}
+
+/// 代码块内容中的全部标注
+pub fn parse_callouts(content: Span) -> Vec<Callout> {
+    let first_line = content.location_line() as usize;
+    let lines = content.fragment().split('\n');
+    let mut callouts = vec![];
+    for (index, (line, (code, numbers))) in lines.zip(split_callouts(content.fragment())).enumerate() {
+        if numbers.is_empty() {
+            continue;
+        }
+        let after = &line[code.len()..];
+        let start = code.len() + after.len() - after.trim_start().len();
+        let position = Position {
+            line: first_line + index,
+            column: line[..start].chars().count() + 1,
+        };
+        let location = Location::covering(position, line[start..].trim_end());
+        callouts.extend(numbers.into_iter().map(|number| Callout {
+            number,
+            line: index + 1,
+            location,
+        }));
+    }
+    callouts
+}
+
+/// `<1>` 或 `<.>`
+fn callout_marker(i: Span) -> IResult<Option<usize>> {
+    delimited(
+        char('<'),
+        alt((
+            value(None, char('.')),
+            map_opt(digit1, |number: Span| number.fragment().parse().ok().map(Some)),
+        )),
+        char('>'),
+    )(i)
+}
+
+/// 标注列表中的一项，说明可以延续到之后的非空行
+fn parse_callout_item(i: Span) -> IResult<(Option<usize>, Span)> {
+    let continuation = preceded(
+        not(alt((
+            parse_blank_line,
+            value((), callout_marker),
+            value((), parse_delimiter_line),
+        ))),
+        parse_line,
+    );
+    let (i, number) = terminated(callout_marker, space1)(i)?;
+    let (i, (text, _)) = consumed(pair(parse_line, many0(continuation)))(i)?;
+    Ok((i, (number, text.slice(..text.fragment().trim_end().len()))))
+}
+
+/// 以 `<1>` 开头的连续行组成的标注列表，各项之间可以有空行
+pub fn parse_callout_list(i: Span<'_>) -> IResult<'_, Block<'_>> {
+    let (i, entries) = many1(terminated(consumed(parse_callout_item), many0(parse_blank_line)))(i)?;
+
+    let mut items = vec![];
+    for (index, (span, (number, text))) in entries.iter().enumerate() {
+        let len = text.location_offset() + text.fragment().len() - span.location_offset();
+        items.push(CalloutItem {
+            number: number.unwrap_or(index + 1),
+            text: text.fragment(),
+            inlines: parse_inlines(*text),
+            location: location(&span.slice(..len)),
+        });
+    }
+    let location = Location {
+        start: items[0].location.start,
+        end: items[items.len() - 1].location.end,
+    };
+    Ok((
+        i,
+        Block {
+            kind: BlockKind::CalloutList { items },
+            metadata: BlockMetadata::default(),
+            location,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use nom::Slice;
+
+    use crate::ast::{BlockKind, Location, Position};
+    use crate::parser::callout::{parse_callout_list, parse_callouts, split_callouts};
+    use crate::parser::Span;
+
+    #[test]
+    pub fn test_split_callouts() {
+        let content = "let x = 1; // <1>\nputs x # <2> <3>\nList<String>\nplain <.>\nescaped \\<4>\n(+ 1 2) ;; <.>";
+        assert_eq!(
+            split_callouts(content),
+            vec![
+                ("let x = 1;", vec![1]),
+                ("puts x", vec![2, 3]),
+                ("List<String>", vec![]),
+                ("plain", vec![1]),
+                ("escaped \\<4>", vec![]),
+                ("(+ 1 2)", vec![2]),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_parse_callouts() {
+        let text = "----\nfn main() { // <1>\n}\n----\n";
+        let callouts = parse_callouts(Span::new(text).slice(5..25));
+        assert_eq!(callouts.len(), 1);
+        assert_eq!(callouts[0].number, 1);
+        assert_eq!(callouts[0].line, 1);
+        assert_eq!(
+            callouts[0].location,
+            Location {
+                start: Position { line: 2, column: 13 },
+                end: Position { line: 2, column: 19 },
+            }
+        );
+    }
+
+    #[test]
+    pub fn test_parse_callout_list() {
+        let text = "<1> First *item*\ncontinued.\n\n<.> Second.\n\nAfter.";
+        let (i, block) = parse_callout_list(Span::new(text)).unwrap();
+        assert_eq!(*i.fragment(), "After.");
+        assert_eq!(block.location.end, Position { line: 4, column: 12 });
+
+        let BlockKind::CalloutList { items } = block.kind else {
+            panic!("expected callout list, got {:?}", block.kind);
+        };
+        assert_eq!(items.len(), 2);
+        assert_eq!((items[0].number, items[0].text), (1, "First *item*\ncontinued."));
+        assert_eq!((items[1].number, items[1].text), (2, "Second."));
+        assert_eq!(items[1].location.start, Position { line: 4, column: 1 });
+
+        assert!(parse_callout_list(Span::new("<b> not a callout\n")).is_err());
+        assert!(parse_callout_list(Span::new("<1>no space\n")).is_err());
+    }
+}
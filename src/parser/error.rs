@@ -0,0 +1,113 @@
+//! 解析器的错误类型
+
+use std::fmt;
+
+use nom::error::{ContextError, ErrorKind};
+use nom::Slice;
+
+use crate::ast::Location;
+use crate::diagnostic::Diagnostic;
+use crate::parser::{position, IResult, Span};
+
+/// 解析失败的原因，外层解析器可以通过 `nom::error::context` 附加说明
+#[derive(Debug, PartialEq)]
+pub struct Error<'a> {
+    /// 出错的位置，生成诊断时会被标记出来
+    pub span: Span<'a>,
+    pub message: String,
+    /// 出错时所在的外层结构，从内到外排列
+    pub context: Vec<(Span<'a>, &'static str)>,
+}
+
+impl<'a> Error<'a> {
+    pub fn new(span: Span<'a>, message: impl Into<String>) -> Self {
+        Error {
+            span,
+            message: message.into(),
+            context: vec![],
+        }
+    }
+
+    pub fn to_diagnostic(&self) -> Diagnostic {
+        let mut diagnostic = Diagnostic::error(
+            self.message.clone(),
+            Location::covering(position(&self.span), self.span.fragment()),
+        );
+        for (span, context) in &self.context {
+            diagnostic
+                .notes
+                .push(format!("while parsing {context} starting at line {}", span.location_line()));
+        }
+        diagnostic
+    }
+}
+
+/// 只标记剩余输入的第一个字符
+fn first_char(input: Span) -> Span {
+    let len = input.fragment().chars().next().map_or(0, char::len_utf8);
+    input.slice(..len)
+}
+
+impl<'a> nom::error::ParseError<Span<'a>> for Error<'a> {
+    fn from_error_kind(input: Span<'a>, kind: ErrorKind) -> Self {
+        let message = match kind {
+            ErrorKind::Eof => "expected end of input",
+            ErrorKind::CrLf => "expected end of line",
+            _ if input.fragment().is_empty() => "unexpected end of input",
+            _ => "unexpected input",
+        };
+        Error::new(first_char(input), message)
+    }
+
+    fn append(_: Span<'a>, _: ErrorKind, other: Self) -> Self {
+        other
+    }
+
+    fn from_char(input: Span<'a>, c: char) -> Self {
+        Error::new(first_char(input), format!("expected `{c}`"))
+    }
+}
+
+impl<'a> ContextError<Span<'a>> for Error<'a> {
+    fn add_context(input: Span<'a>, context: &'static str, mut other: Self) -> Self {
+        other.context.push((input, context));
+        other
+    }
+}
+
+/// 与 `nom::combinator::cut` 相同，但使用 `message` 作为出错原因
+pub fn expect<'a, O>(
+    message: &'static str,
+    mut parser: impl FnMut(Span<'a>) -> IResult<'a, O>,
+) -> impl FnMut(Span<'a>) -> IResult<'a, O> {
+    move |i| {
+        parser(i).map_err(|err| match err {
+            nom::Err::Error(err) => nom::Err::Failure(Error {
+                message: message.to_string(),
+                ..err
+            }),
+            err => err,
+        })
+    }
+}
+
+/// 无法恢复的解析错误，包含解析过程中产生的全部诊断
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut diagnostics = self.diagnostics.iter();
+        if let Some(diagnostic) = diagnostics.next() {
+            write!(f, "{diagnostic}")?;
+        }
+        for diagnostic in diagnostics {
+            write!(f, "\n{diagnostic}")?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for ParseError {}
@@ -0,0 +1,427 @@
+use nom::branch::alt;
+use nom::bytes::complete::{is_not, take_till1, take_while, take_while1};
+use nom::character::complete::{char, line_ending, space0, space1};
+use nom::combinator::{consumed, map, not, opt, verify};
+use nom::error::context;
+use nom::multi::{many0, separated_list1};
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
+use nom::Slice;
+
+use crate::ast::{Attribute, AuthorInfo, Header, Name, Revision};
+use crate::parser::error::expect;
+use crate::parser::{eol, location, IResult, Span};
+
+pub fn name(input: Span<'_>) -> IResult<'_, &str> {
+    map(is_not("\r\n\t <;"), |name: Span| *name.fragment())(input)
+}
+
+/// 解析单个作者：姓名和可选的邮箱
+pub fn parse_author(i: Span<'_>) -> IResult<'_, AuthorInfo<'_>> {
+    let auth = tuple((
+        terminated(name, space0),
+        opt(terminated(name, space0)),
+        opt(terminated(name, space0)),
+    ));
+    let email = terminated(
+        preceded(
+            char('<'),
+            expect(
+                "expected `>` to close the email address",
+                terminated(is_not(">\r\n"), char('>')),
+            ),
+        ),
+        space0,
+    );
+
+    let (i, ((firstname, middlename, lastname), email)) =
+        preceded(pair(not(char(':')), space0), pair(auth, opt(email)))(i)?;
+
+    Ok((
+        i,
+        AuthorInfo {
+            author: Name {
+                firstname,
+                middle_name: lastname.and(middlename),
+                lastname: lastname.or(middlename),
+            },
+            email: email.map(|email| *email.fragment()),
+        },
+    ))
+}
+
+/// 解析文档头部的作者信息
+pub fn parse_author_line(i: Span<'_>) -> IResult<'_, AuthorInfo<'_>> {
+    terminated(parse_author, line_ending)(i)
+}
+
+/// 解析以 `;` 分隔多个作者的作者行
+pub fn parse_author_list(i: Span<'_>) -> IResult<'_, Vec<AuthorInfo<'_>>> {
+    context(
+        "the author line",
+        terminated(separated_list1(char(';'), parse_author), line_ending),
+    )(i)
+}
+
+/// 只有版本号时必须以 `v` 开头，例如 `v1.0`
+pub fn single_revnumber(input: Span<'_>) -> IResult<'_, &str> {
+    map(
+        delimited(
+            char('v'),
+            take_till1(|c: char| c.is_whitespace() || c == ':'),
+            space0,
+        ),
+        |number: Span| *number.fragment(),
+    )(input)
+}
+
+/// 逗号之前的版本号，忽略开头的非数字字符（如 `v`、`version`）
+pub fn parse_revnumber(input: Span<'_>) -> IResult<'_, &str> {
+    map(
+        preceded(
+            take_while(|c: char| !c.is_ascii_digit() && !",\r\n".contains(c)),
+            take_till1(|c| ",\r\n".contains(c)),
+        ),
+        |number: Span| number.fragment().trim_end(),
+    )(input)
+}
+
+pub fn parse_revdata(input: Span<'_>) -> IResult<'_, &str> {
+    map(
+        verify(
+            preceded(space0, take_till1(|c| ":\r\n".contains(c))),
+            |date: &Span| !date.trim().is_empty(),
+        ),
+        |date: Span| date.fragment().trim_end(),
+    )(input)
+}
+
+pub fn parse_revremark(input: Span<'_>) -> IResult<'_, &str> {
+    map(
+        preceded(pair(char(':'), space0), is_not("\r\n")),
+        |remark: Span| remark.fragment().trim_end(),
+    )(input)
+}
+/// 解析修订行：`v1.0`、`1.0, 2024-01-01` 或 `1.0, 2024-01-01: remark`
+pub fn parse_revision(input: Span<'_>) -> IResult<'_, Revision<'_>> {
+    let full = map(
+        tuple((parse_revnumber, char(','), parse_revdata, opt(parse_revremark))),
+        |(number, _, date, remark)| Revision {
+            number: Some(number),
+            date: Some(date),
+            remark,
+        },
+    );
+    let number_only = map(
+        pair(single_revnumber, opt(parse_revremark)),
+        |(number, remark)| Revision {
+            number: Some(number),
+            date: None,
+            remark,
+        },
+    );
+    let date_only = map(pair(parse_revdata, opt(parse_revremark)), |(date, remark)| {
+        Revision {
+            number: None,
+            date: Some(date),
+            remark,
+        }
+    });
+
+    preceded(
+        not(char(':')),
+        terminated(alt((full, number_only, date_only)), eol),
+    )(input)
+}
+
+pub fn parse_doc_header(i: Span<'_>) -> IResult<'_, Header<'_>> {
+    let start = i;
+    let (i, title) = preceded(
+        pair(char('='), space1),
+        terminated(is_not("\r\n"), eol),
+    )(i)?;
+
+    let (i, auth_info) = opt(parse_author_list)(i)?;
+    // 修订行只能紧跟在作者行之后
+    let (i, revision) = match auth_info {
+        Some(_) => opt(parse_revision)(i)?,
+        None => (i, None),
+    };
+    let auth_info = auth_info.unwrap_or_default();
+    let (i, attrs) = many0(terminated(parse_doc_attr, eol))(i)?;
+
+    let len = i.location_offset() - start.location_offset();
+    Ok((
+        i,
+        Header {
+            title: title.fragment(),
+            auth_info,
+            revision,
+            attrs,
+            location: location(&start.slice(..len)),
+        },
+    ))
+}
+
+/// 解析文档属性
+pub fn parse_doc_attr(i: Span<'_>) -> IResult<'_, Attribute<'_>> {
+    let name = delimited(
+        preceded(char(':'), space0),
+        pair(opt(char('!')), take_while1(|c| !":\r\n".contains(c))),
+        char(':'),
+    );
+
+    let value = preceded(space1, is_not("\r\n"));
+
+    let (i, (span, ((unset, name), value))) = consumed(pair(name, opt(value)))(i)?;
+
+    Ok((
+        i,
+        Attribute {
+            unset: unset.is_some(),
+            name: name.fragment(),
+            value: value.map(|value: Span| *value.fragment()),
+            location: location(&span),
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{AuthorInfo, Location, Name, Position, Revision};
+    use crate::parser::Span;
+    use crate::parser::header::{parse_author_line, parse_author_list, parse_doc_attr, parse_doc_header, parse_revision};
+
+    #[test]
+    pub fn test_parse_attr() {
+        let (_, attrs) = parse_doc_attr(Span::new(":hello: world\r\n")).unwrap();
+        assert_eq!(attrs.name, "hello");
+        assert_eq!(attrs.value, Some("world"));
+        assert_eq!(
+            attrs.location,
+            Location {
+                start: Position { line: 1, column: 1 },
+                end: Position { line: 1, column: 14 },
+            }
+        );
+    }
+
+    fn assert_parse_auth_line(input: &str, expected: &(&str, AuthorInfo)) -> Result<(), String> {
+        let (i, auth_info) = parse_author_line(Span::new(input)).unwrap();
+        if (*i.fragment(), auth_info) != *expected {
+            Err("".to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    #[test]
+    pub fn test_parse_auth_line() {
+        let test_data = [
+            (
+                "Wang\n",
+                (
+                    "",
+                    AuthorInfo {
+                        author: Name {
+                            firstname: "Wang",
+                            middle_name: None,
+                            lastname: None,
+                        },
+                        email: None,
+                    },
+                ),
+            ),
+            (
+                "Wang \n",
+                (
+                    "",
+                    AuthorInfo {
+                        author: Name {
+                            firstname: "Wang",
+                            middle_name: None,
+                            lastname: None,
+                        },
+                        email: None,
+                    },
+                ),
+            ),
+            (
+                "Wang Heng\n",
+                (
+                    "",
+                    AuthorInfo {
+                        author: Name {
+                            firstname: "Wang",
+                            middle_name: None,
+                            lastname: Some("Heng"),
+                        },
+                        email: None,
+                    },
+                ),
+            ),
+            (
+                "Wang Heng \n",
+                (
+                    "",
+                    AuthorInfo {
+                        author: Name {
+                            firstname: "Wang",
+                            middle_name: None,
+                            lastname: Some("Heng"),
+                        },
+                        email: None,
+                    },
+                ),
+            ),
+            (
+                "Wang Yue Heng\n",
+                (
+                    "",
+                    AuthorInfo {
+                        author: Name {
+                            firstname: "Wang",
+                            middle_name: Some("Yue"),
+                            lastname: Some("Heng"),
+                        },
+                        email: None,
+                    },
+                ),
+            ),
+            (
+                "Wang Yue Heng \n",
+                (
+                    "",
+                    AuthorInfo {
+                        author: Name {
+                            firstname: "Wang",
+                            middle_name: Some("Yue"),
+                            lastname: Some("Heng"),
+                        },
+                        email: None,
+                    },
+                ),
+            ),
+            (
+                "Wang Yue Heng <admin@eastack.me>\n",
+                (
+                    "",
+                    AuthorInfo {
+                        author: Name {
+                            firstname: "Wang",
+                            middle_name: Some("Yue"),
+                            lastname: Some("Heng"),
+                        },
+                        email: Some("admin@eastack.me"),
+                    },
+                ),
+            ),
+            (
+                "Wang Yue Heng <admin@eastack.me> \n",
+                (
+                    "",
+                    AuthorInfo {
+                        author: Name {
+                            firstname: "Wang",
+                            middle_name: Some("Yue"),
+                            lastname: Some("Heng"),
+                        },
+                        email: Some("admin@eastack.me"),
+                    },
+                ),
+            ),
+        ];
+
+        test_data
+            .iter()
+            .try_for_each(|(input, expected)| assert_parse_auth_line(input, expected))
+            .unwrap();
+    }
+
+    #[test]
+    pub fn test_parse_revision() {
+        let test_data = [
+            ("v1.0\n", Revision { number: Some("1.0"), date: None, remark: None }),
+            ("v1.0: First draft\n", Revision { number: Some("1.0"), date: None, remark: Some("First draft") }),
+            ("1.0, 2024-01-01\n", Revision { number: Some("1.0"), date: Some("2024-01-01"), remark: None }),
+            ("v2.1, 2024-01-01\n", Revision { number: Some("2.1"), date: Some("2024-01-01"), remark: None }),
+            (
+                "1.0, 2024-01-01: Initial release \n",
+                Revision { number: Some("1.0"), date: Some("2024-01-01"), remark: Some("Initial release") },
+            ),
+            ("2024-01-01\n", Revision { number: None, date: Some("2024-01-01"), remark: None }),
+        ];
+
+        for (input, expected) in test_data {
+            let (i, revision) = parse_revision(Span::new(input)).unwrap();
+            assert_eq!(*i.fragment(), "", "input: {input:?}");
+            assert_eq!(revision, expected, "input: {input:?}");
+        }
+
+        assert!(parse_revision(Span::new(":toc: left\n")).is_err());
+    }
+
+    #[test]
+    pub fn test_parse_doc_header_revision() {
+        let (i, header) = parse_doc_header(Span::new("= Title\nAuthor Name\n1.0, 2024-01-01: remark\n:toc:\n")).unwrap();
+        assert_eq!(*i.fragment(), "");
+        assert_eq!(
+            header.revision,
+            Some(Revision { number: Some("1.0"), date: Some("2024-01-01"), remark: Some("remark") })
+        );
+        assert_eq!(header.attrs.len(), 1);
+        assert_eq!(header.attrs[0].location.start, Position { line: 4, column: 1 });
+        assert_eq!(header.location.end, Position { line: 4, column: 6 });
+
+        let (_, header) = parse_doc_header(Span::new("= Title\n:toc:\n")).unwrap();
+        assert!(header.auth_info.is_empty());
+        assert_eq!(header.revision, None);
+    }
+
+    #[test]
+    pub fn test_parse_author_list() {
+        let (i, authors) = parse_author_list(Span::new("A One <a@x>; B Middle Two <b@y>;C\n")).unwrap();
+        assert_eq!(*i.fragment(), "");
+        assert_eq!(
+            authors,
+            vec![
+                AuthorInfo {
+                    author: Name { firstname: "A", middle_name: None, lastname: Some("One") },
+                    email: Some("a@x"),
+                },
+                AuthorInfo {
+                    author: Name { firstname: "B", middle_name: Some("Middle"), lastname: Some("Two") },
+                    email: Some("b@y"),
+                },
+                AuthorInfo {
+                    author: Name { firstname: "C", middle_name: None, lastname: None },
+                    email: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_author_attributes() {
+        let (_, header) = parse_doc_header(Span::new("= Title\nA One <a@x>; B Middle Two <b@y>\n")).unwrap();
+        let attrs = header.author_attributes();
+        let get = |name: &str| {
+            attrs
+                .iter()
+                .find(|(key, _)| key == name)
+                .map(|(_, value)| value.as_str())
+        };
+
+        assert_eq!(get("author"), Some("A One"));
+        assert_eq!(get("author_1"), Some("A One"));
+        assert_eq!(get("email"), Some("a@x"));
+        assert_eq!(get("authorinitials"), Some("AO"));
+        assert_eq!(get("firstname_2"), Some("B"));
+        assert_eq!(get("middlename_2"), Some("Middle"));
+        assert_eq!(get("lastname_2"), Some("Two"));
+        assert_eq!(get("email_2"), Some("b@y"));
+        assert_eq!(get("authorinitials_2"), Some("BMT"));
+        assert_eq!(get("authors"), Some("A One, B Middle Two"));
+        assert_eq!(get("authorcount"), Some("2"));
+        assert_eq!(get("middlename"), None);
+    }
+}
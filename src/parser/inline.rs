@@ -0,0 +1,446 @@
+use std::borrow::Cow;
+
+use nom::branch::alt;
+use nom::bytes::complete::{tag, take_till1, take_until};
+use nom::character::complete::{char, multispace1, one_of};
+use nom::combinator::{map, map_opt, opt, verify};
+use nom::error::{ErrorKind, ParseError};
+use nom::sequence::{delimited, pair, preceded};
+use nom::Slice;
+
+use crate::ast::{FormattedText, Inline, InlineKind, Xref};
+use crate::parser::error::Error;
+use crate::parser::{location, IResult, Span};
+
+pub fn parse_strong_formatting_pair(i: Span<'_>) -> IResult<'_, FormattedText<'_>> {
+    delimited(
+        multispace1,
+        // space1,
+        verify(parse_formatted_text, |text| matches!(text, FormattedText::Strong(_))),
+        one_of(",;\".?! \t"),
+    )(i)
+}
+
+/// 将一整段文本解析为纯文本和格式化文本交替的行内节点
+///
+/// 约束形式（`*x*`）的边界规则与 AsciiDoc 一致：开始标记之前不能是单词字符，
+/// 结束标记之后也不能是单词字符，因此 `a*b*c` 保持原样。
+pub fn parse_inlines(i: Span<'_>) -> Vec<Inline<'_>> {
+    let text = i.fragment();
+    let mut inlines = vec![];
+    let mut offset = 0;
+    let mut text_start = 0;
+    let mut prev = None;
+    let closers = Closers::new(text);
+
+    while let Some(c) = text[offset..].chars().next() {
+        if !closers.may_close(c, offset) {
+            prev = Some(c);
+            offset += c.len_utf8();
+            continue;
+        }
+
+        let rest = i.slice(offset..);
+        let parsed = if can_open_constrained(c, prev) {
+            alt((map(parse_xref, InlineKind::Xref), map(parse_formatted_text, InlineKind::Formatted)))(rest)
+        } else {
+            alt((map(parse_xref, InlineKind::Xref), map(parse_unconstrained_text, InlineKind::Formatted)))(rest)
+        };
+
+        match parsed {
+            Ok((remaining, kind)) => {
+                if text_start < offset {
+                    inlines.push(text_inline(i.slice(text_start..offset)));
+                }
+                let end = remaining.location_offset() - i.location_offset();
+                inlines.push(Inline {
+                    kind,
+                    location: location(&i.slice(offset..end)),
+                });
+
+                prev = text[..end].chars().next_back();
+                offset = end;
+                text_start = end;
+            }
+            Err(_) => {
+                prev = Some(c);
+                offset += c.len_utf8();
+            }
+        }
+    }
+
+    if text_start < text.len() {
+        inlines.push(text_inline(i.slice(text_start..)));
+    }
+    inlines
+}
+
+/// 各种结束标记在文本中最后出现的位置
+///
+/// 开始标记之后没有结束标记时不必尝试解析，这样每个位置只需常数时间，
+/// 避免在没有结束标记的长文本中反复扫描到末尾。
+struct Closers {
+    /// `*`、`_`、`` ` ``、`#` 最后一个能结束约束形式的位置
+    constrained: [Option<usize>; 4],
+    /// `**`、`__`、` `` `、`##` 最后出现的位置
+    unconstrained: [Option<usize>; 4],
+    /// `>>` 最后出现的位置
+    shorthand: Option<usize>,
+    /// `]` 最后出现的位置
+    bracket: Option<usize>,
+}
+
+const MARKS: [char; 4] = ['*', '_', '`', '#'];
+const DOUBLED_MARKS: [&str; 4] = ["**", "__", "``", "##"];
+
+impl Closers {
+    fn new(text: &str) -> Self {
+        let mut constrained = [None; 4];
+        let mut prev = None;
+        let mut chars = text.char_indices().peekable();
+        while let Some((index, c)) = chars.next() {
+            if let Some(mark) = MARKS.iter().position(|&mark| mark == c) {
+                let next = chars.peek().map(|&(_, next)| next);
+                if prev.is_some_and(|prev: char| !prev.is_whitespace()) && can_close_constrained(c, next) {
+                    constrained[mark] = Some(index);
+                }
+            }
+            prev = Some(c);
+        }
+        Closers {
+            constrained,
+            unconstrained: DOUBLED_MARKS.map(|mark| text.rfind(mark)),
+            shorthand: text.rfind(">>"),
+            bracket: text.rfind(']'),
+        }
+    }
+
+    /// 位于 `offset` 的字符 `c` 之后是否可能有对应的结束标记
+    fn may_close(&self, c: char, offset: usize) -> bool {
+        let after = |last: Option<usize>, len: usize| last.is_some_and(|last| last >= offset + len);
+        match c {
+            '<' => after(self.shorthand, 2),
+            'x' => after(self.bracket, 5),
+            '^' | '~' => true,
+            _ => match MARKS.iter().position(|&mark| mark == c) {
+                Some(mark) => after(self.constrained[mark], 2) || after(self.unconstrained[mark], 2),
+                None => false,
+            },
+        }
+    }
+}
+
+fn text_inline(span: Span<'_>) -> Inline<'_> {
+    Inline {
+        kind: InlineKind::Text(span.fragment()),
+        location: location(&span),
+    }
+}
+
+/// 交叉引用：`<<id>>`、`<<id,text>>`、`xref:id[text]` 和 `xref:file.adoc#id[text]`
+pub fn parse_xref(i: Span<'_>) -> IResult<'_, Xref<'_>> {
+    // 目标中不能有空白和尖括号，遇到时立即失败，不必扫描到 `>>`
+    let shorthand = map_opt(
+        delimited(
+            tag("<<"),
+            pair(
+                take_till1(|c: char| c == ',' || c == '<' || c == '>' || c.is_whitespace()),
+                opt(preceded(char(','), take_until(">>"))),
+            ),
+            tag(">>"),
+        ),
+        |(target, text): (Span<'_>, Option<Span<'_>>)| {
+            xref(target.fragment(), Some(text.map_or("", |text| text.fragment().trim())))
+        },
+    );
+    let r#macro = map_opt(
+        pair(
+            preceded(tag("xref:"), take_till1(|c: char| c == '[' || c == ':' || c.is_whitespace())),
+            delimited(char('['), take_until("]"), char(']')),
+        ),
+        |(target, text): (Span<'_>, Span<'_>)| xref(target.fragment(), Some(text.fragment())),
+    );
+    alt((shorthand, r#macro))(i)
+}
+
+/// 拆分引用目标，`#` 之前或以 `.adoc` 结尾的是其他文档的路径
+fn xref<'a>(target: &'a str, text: Option<&'a str>) -> Option<Xref<'a>> {
+    if target.is_empty() || target.contains(char::is_whitespace) {
+        return None;
+    }
+    let (path, id) = match target.split_once('#') {
+        Some((path, id)) => (Some(path).filter(|path| !path.is_empty()), Some(id).filter(|id| !id.is_empty())),
+        None if target.ends_with(".adoc") => (Some(target), None),
+        None => (None, Some(target)),
+    };
+    Some(Xref {
+        path,
+        id,
+        text: text.filter(|text| !text.is_empty()).map(Cow::Borrowed),
+    })
+}
+
+/// 解析一段格式化文本，无约束形式（`**x**`）优先于约束形式（`*x*`）
+pub fn parse_formatted_text(i: Span<'_>) -> IResult<'_, FormattedText<'_>> {
+    alt((
+        map(unconstrained("**"), FormattedText::Strong),
+        map(constrained('*'), FormattedText::Strong),
+        map(unconstrained("__"), FormattedText::Emphasis),
+        map(constrained('_'), FormattedText::Emphasis),
+        map(unconstrained("``"), FormattedText::Monospace),
+        map(constrained('`'), FormattedText::Monospace),
+        map(unconstrained("##"), FormattedText::Highlight),
+        map(constrained('#'), FormattedText::Highlight),
+        // 上标和下标本身就可以出现在单词中间，但内容不能包含空白
+        map(unbroken('^'), FormattedText::Superscript),
+        map(unbroken('~'), FormattedText::Subscript),
+    ))(i)
+}
+
+/// 只解析可以出现在单词中间的格式化文本
+fn parse_unconstrained_text(i: Span<'_>) -> IResult<'_, FormattedText<'_>> {
+    alt((
+        map(unconstrained("**"), FormattedText::Strong),
+        map(unconstrained("__"), FormattedText::Emphasis),
+        map(unconstrained("``"), FormattedText::Monospace),
+        map(unconstrained("##"), FormattedText::Highlight),
+        map(unbroken('^'), FormattedText::Superscript),
+        map(unbroken('~'), FormattedText::Subscript),
+    ))(i)
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+/// 约束形式的开始标记 `mark` 能否紧跟在 `prev` 之后
+fn can_open_constrained(mark: char, prev: Option<char>) -> bool {
+    let excluded = if mark == '`' { ";:}\"'`" } else { ";:}" };
+    prev.is_none_or(|c| !is_word_char(c) && !excluded.contains(c))
+}
+
+/// 约束形式的结束标记之后能否是 `next`
+fn can_close_constrained(mark: char, next: Option<char>) -> bool {
+    let excluded = if mark == '`' { "\"'`" } else { "" };
+    next.is_none_or(|c| !is_word_char(c) && !excluded.contains(c))
+}
+
+/// `**x**`：内容不能为空
+fn unconstrained<'a>(mark: &'static str) -> impl FnMut(Span<'a>) -> IResult<'a, Vec<Inline<'a>>> {
+    move |i| {
+        let (i, content) = delimited(
+            tag(mark),
+            verify(take_until(mark), |content: &Span| !content.is_empty()),
+            tag(mark),
+        )(i)?;
+        Ok((i, parse_inlines(content)))
+    }
+}
+
+/// `*x*`：内容的首尾不能是空白，结束标记之后不能是单词字符
+fn constrained<'a>(mark: char) -> impl FnMut(Span<'a>) -> IResult<'a, Vec<Inline<'a>>> {
+    move |i| {
+        let (rest, _) = char(mark)(i)?;
+        let text = rest.fragment();
+        if text.starts_with(char::is_whitespace) {
+            return Err(nom::Err::Error(Error::from_error_kind(i, ErrorKind::Char)));
+        }
+
+        let close = text.char_indices().skip(1).find(|&(index, c)| {
+            c == mark
+                && !text[..index].ends_with(char::is_whitespace)
+                && can_close_constrained(mark, text[index + 1..].chars().next())
+        });
+
+        match close {
+            Some((index, _)) => Ok((rest.slice(index + 1..), parse_inlines(rest.slice(..index)))),
+            None => Err(nom::Err::Error(Error::from_error_kind(i, ErrorKind::Char))),
+        }
+    }
+}
+
+/// `^x^`：内容不能包含空白
+fn unbroken<'a>(mark: char) -> impl FnMut(Span<'a>) -> IResult<'a, Vec<Inline<'a>>> {
+    move |i| {
+        let (i, content) = delimited(
+            char(mark),
+            take_till1(|c: char| c == mark || c.is_whitespace()),
+            char(mark),
+        )(i)?;
+        Ok((i, parse_inlines(content)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{FormattedText, Inline, InlineKind, Location, Position, Xref};
+    use crate::parser::inline::{parse_formatted_text, parse_inlines, parse_strong_formatting_pair, parse_xref};
+    use crate::parser::Span;
+
+    /// 以紧凑的文本形式描述行内节点的结构，便于忽略位置进行比较
+    fn tree(inlines: &[Inline]) -> String {
+        inlines
+            .iter()
+            .map(|inline| match &inline.kind {
+                InlineKind::Text(text) => format!("{text:?}"),
+                InlineKind::Formatted(text) => formatted(text),
+                InlineKind::Xref(xref) => format!("Xref[{:?}, {:?}, {:?}]", xref.path, xref.id, xref.text),
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn formatted(text: &FormattedText) -> String {
+        let (name, children) = match text {
+            FormattedText::Strong(children) => ("Strong", children),
+            FormattedText::Emphasis(children) => ("Emphasis", children),
+            FormattedText::Monospace(children) => ("Monospace", children),
+            FormattedText::Highlight(children) => ("Highlight", children),
+            FormattedText::Superscript(children) => ("Superscript", children),
+            FormattedText::Subscript(children) => ("Subscript", children),
+        };
+        format!("{name}[{}]", tree(children))
+    }
+
+    fn inlines(text: &str) -> String {
+        tree(&parse_inlines(Span::new(text)))
+    }
+
+    #[test]
+    pub fn test_parse_strong_text() {
+        let text = "strong";
+        let doc = format!(" *{}* ", text);
+        let (i, strong_text) = parse_strong_formatting_pair(Span::new(&doc)).unwrap();
+        assert_eq!(*i.fragment(), "");
+        assert_eq!(formatted(&strong_text), "Strong[\"strong\"]");
+
+        let text = "Hello World";
+        let doc = format!(" *{}*!", text);
+        let (i, strong_text) = parse_strong_formatting_pair(Span::new(&doc)).unwrap();
+        assert_eq!(*i.fragment(), "");
+        assert_eq!(formatted(&strong_text), "Strong[\"Hello World\"]");
+    }
+
+    #[test]
+    pub fn test_parse_formatted_text() {
+        let test_data = [
+            ("_emphasis_", "Emphasis[\"emphasis\"]"),
+            ("__emph__asis", "Emphasis[\"emph\"]"),
+            ("`mono`", "Monospace[\"mono\"]"),
+            ("``mo``no", "Monospace[\"mo\"]"),
+            ("#mark#", "Highlight[\"mark\"]"),
+            ("##ma##rk", "Highlight[\"ma\"]"),
+            ("^super^", "Superscript[\"super\"]"),
+            ("~sub~", "Subscript[\"sub\"]"),
+            ("**st**rong", "Strong[\"st\"]"),
+        ];
+
+        for (input, expected) in test_data {
+            let (_, text) = parse_formatted_text(Span::new(input)).unwrap();
+            assert_eq!(formatted(&text), expected, "input: {input}");
+        }
+    }
+
+    #[test]
+    pub fn test_parse_formatted_text_rejects_spaces() {
+        assert!(parse_formatted_text(Span::new("* not strong*")).is_err());
+        assert!(parse_formatted_text(Span::new("*not strong *")).is_err());
+        assert!(parse_formatted_text(Span::new("^not super^")).is_err());
+        assert!(parse_formatted_text(Span::new("**")).is_err());
+    }
+
+    #[test]
+    pub fn test_parse_nested_formatted_text() {
+        let (i, text) = parse_formatted_text(Span::new("*bold _and italic_ text*")).unwrap();
+        assert_eq!(*i.fragment(), "");
+        assert_eq!(
+            formatted(&text),
+            "Strong[\"bold \", Emphasis[\"and italic\"], \" text\"]"
+        );
+
+        let (_, text) = parse_formatted_text(Span::new("**__bold italic__**")).unwrap();
+        assert_eq!(formatted(&text), "Strong[Emphasis[\"bold italic\"]]");
+    }
+
+    #[test]
+    pub fn test_parse_inlines() {
+        assert_eq!(
+            inlines("*Bold* at start, `mono` and _emph_.\nEnd is **strong**"),
+            "Strong[\"Bold\"], \" at start, \", Monospace[\"mono\"], \" and \", \
+             Emphasis[\"emph\"], \".\\nEnd is \", Strong[\"strong\"]"
+        );
+    }
+
+    #[test]
+    pub fn test_parse_inlines_location() {
+        let inlines = parse_inlines(Span::new("Line one\nthen *bold* text"));
+        let locations: Vec<Location> = inlines.iter().map(|inline| inline.location).collect();
+        let at = |line, column| Position { line, column };
+        assert_eq!(
+            locations,
+            vec![
+                Location { start: at(1, 1), end: at(2, 6) },
+                Location { start: at(2, 6), end: at(2, 12) },
+                Location { start: at(2, 12), end: at(2, 17) },
+            ]
+        );
+
+        let InlineKind::Formatted(FormattedText::Strong(children)) = &inlines[1].kind else {
+            panic!("expected strong, got {:?}", inlines[1]);
+        };
+        assert_eq!(children[0].location, Location { start: at(2, 7), end: at(2, 11) });
+    }
+
+    #[test]
+    pub fn test_parse_inlines_word_boundary() {
+        assert_eq!(inlines("a*b*c"), "\"a*b*c\"");
+        assert_eq!(inlines("snake_case_name"), "\"snake_case_name\"");
+        assert_eq!(inlines("*a*b"), "\"*a*b\"");
+        assert_eq!(inlines("a**b**c"), "\"a\", Strong[\"b\"], \"c\"");
+        assert_eq!(inlines("E=mc^2^"), "\"E=mc\", Superscript[\"2\"]");
+    }
+
+    #[test]
+    pub fn test_parse_xref() {
+        let xref = |text| parse_xref(Span::new(text)).map(|(_, xref)| xref).ok();
+        assert_eq!(
+            xref("<<install>>"),
+            Some(Xref {
+                path: None,
+                id: Some("install"),
+                text: None,
+            })
+        );
+        assert_eq!(xref("<<install, Getting *started*>>").unwrap().text.as_deref(), Some("Getting *started*"));
+        assert_eq!(
+            xref("xref:guide.adoc#setup[Setup]"),
+            Some(Xref {
+                path: Some("guide.adoc"),
+                id: Some("setup"),
+                text: Some("Setup".into()),
+            })
+        );
+        assert_eq!(xref("<<guide.adoc>>").unwrap().path, Some("guide.adoc"));
+        assert_eq!(xref("xref:faq[]").unwrap().text, None);
+        assert_eq!(xref("<<not an id>>"), None);
+        assert_eq!(xref("<<>>"), None);
+
+        assert_eq!(
+            inlines("See <<a>> and *xref:b[B]*."),
+            "\"See \", Xref[None, Some(\"a\"), None], \" and \", \
+             Strong[Xref[None, Some(\"b\"), Some(\"B\")]], \".\""
+        );
+    }
+
+    #[test]
+    pub fn test_parse_inlines_without_closers() {
+        // 没有结束标记的长文本不能在每个位置都扫描到末尾
+        for pattern in ["*a ", "_a ", "^a ", "<<a ", "<<a, ", "xref:a", "`a "] {
+            let text = pattern.repeat(20_000);
+            assert_eq!(inlines(&text), format!("{text:?}"), "pattern: {pattern:?}");
+        }
+
+        let text = format!("{}b*", "*a ".repeat(20_000));
+        assert_eq!(inlines(&text), format!("Strong[{:?}]", &text[1..text.len() - 1]));
+    }
+}
@@ -0,0 +1,381 @@
+//! 无序列表、有序列表、清单和描述列表
+
+use std::iter::Peekable;
+use std::mem::take;
+use std::vec::IntoIter;
+
+use nom::branch::alt;
+use nom::bytes::complete::tag;
+use nom::character::complete::{char, digit1, not_line_ending, one_of, space0, space1};
+use nom::combinator::{map, map_opt, not, opt, recognize, value};
+use nom::error::{ErrorKind, ParseError};
+use nom::multi::{many0, many1, many_m_n};
+use nom::sequence::{delimited, pair, preceded, terminated, tuple};
+use nom::Slice;
+
+use crate::ast::{Block, BlockKind, BlockMetadata, DescriptionItem, ListItem, Location, Position};
+use crate::parser::block::{parse_blank_line, parse_delimited_block, parse_delimiter_line, parse_line};
+use crate::parser::error::Error;
+use crate::parser::inline::parse_inlines;
+use crate::parser::metadata::{parse_block_anchor, parse_block_attributes, parse_block_title};
+use crate::parser::{eol, location, trim, IResult, Span};
+
+/// 描述列表的标记，较长的在前
+const DESCRIPTION_MARKERS: [&str; 4] = ["::::", ":::", "::", ";;"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MarkerKind {
+    Unordered,
+    Ordered,
+    Description,
+}
+
+/// 列表标记，`key` 相同的项属于同一个列表
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Marker<'a> {
+    /// `*`、`**`、`-`、`.`、`..`、`::`、`;;` 等，所有数字序号都是 `1.`
+    key: &'a str,
+    kind: MarkerKind,
+    number: Option<usize>,
+}
+
+/// 扁平的列表项，由 [`build_list`] 按标记组装成嵌套的列表
+#[derive(Debug, PartialEq)]
+pub struct ListEntry<'a> {
+    marker: Marker<'a>,
+    /// 描述列表项的术语
+    term: Option<Span<'a>>,
+    checked: Option<bool>,
+    /// 描述列表项可以没有说明文字
+    text: Option<Span<'a>>,
+    location: Location,
+    /// 用 `+` 附加的块的原文，由调用者解析
+    attached: Vec<Span<'a>>,
+    /// 包括附加块在内的结束位置
+    end: Position,
+}
+
+fn list_marker(i: Span<'_>) -> IResult<'_, Marker<'_>> {
+    fn marker(kind: MarkerKind) -> impl Fn(Span<'_>) -> Marker<'_> {
+        move |key| Marker {
+            key: key.fragment(),
+            kind,
+            number: None,
+        }
+    }
+    alt((
+        map(recognize(many_m_n(1, 5, char('*'))), marker(MarkerKind::Unordered)),
+        map(tag("-"), marker(MarkerKind::Unordered)),
+        map(recognize(many_m_n(1, 5, char('.'))), marker(MarkerKind::Ordered)),
+        map_opt(terminated(digit1, char('.')), |number: Span| {
+            Some(Marker {
+                key: "1.",
+                kind: MarkerKind::Ordered,
+                number: Some(number.fragment().parse().ok()?),
+            })
+        }),
+    ))(i)
+}
+
+/// 描述列表项的术语和标记，标记之后必须是空白或行尾
+fn description_term(i: Span<'_>) -> IResult<'_, (Span<'_>, Marker<'_>)> {
+    let (_, line) = not_line_ending(i)?;
+    let text = *line.fragment();
+    for (index, _) in text.match_indices([':', ';']) {
+        let Some(key) = DESCRIPTION_MARKERS.into_iter().find(|key| text[index..].starts_with(key)) else {
+            continue;
+        };
+        let after = &text[index + key.len()..];
+        if text[..index].trim().is_empty() || !(after.is_empty() || after.starts_with([' ', '\t'])) {
+            continue;
+        }
+        let marker = Marker {
+            key,
+            kind: MarkerKind::Description,
+            number: None,
+        };
+        let term = line.slice(..text[..index].trim_end().len());
+        return Ok((i.slice(index + key.len()..), (term, marker)));
+    }
+    Err(nom::Err::Error(Error::from_error_kind(i, ErrorKind::Tag)))
+}
+
+/// 列表项的开头：可选的缩进、列表标记和至少一个空格，或者描述列表的术语和标记
+fn item_start(i: Span<'_>) -> IResult<'_, (Option<Span<'_>>, Marker<'_>)> {
+    preceded(
+        space0,
+        alt((
+            map(terminated(list_marker, space1), |marker| (None, marker)),
+            map(terminated(description_term, space0), |(term, marker)| (Some(term), marker)),
+        )),
+    )(i)
+}
+
+/// `[ ]` 为未完成，`[x]` 或 `[*]` 为已完成
+fn checkbox(i: Span) -> IResult<bool> {
+    map(
+        terminated(delimited(char('['), one_of(" xX*"), char(']')), space1),
+        |c| c != ' ',
+    )(i)
+}
+
+/// 只包含 `+` 的行，把之后的块附加到当前列表项上
+fn parse_continuation(i: Span) -> IResult<()> {
+    value((), tuple((char('+'), space0, eol)))(i)
+}
+
+/// 列表项文字或附加段落中的一行，遇到空行、列表项、`+` 或分隔行时结束
+fn text_line(i: Span) -> IResult<Span> {
+    preceded(
+        not(alt((
+            parse_blank_line,
+            value((), item_start),
+            parse_continuation,
+            value((), parse_delimiter_line),
+        ))),
+        parse_line,
+    )(i)
+}
+
+/// `+` 之后附加的块，可以带有元数据行
+fn attached_block(i: Span) -> IResult<Span> {
+    recognize(pair(
+        many0(alt((parse_block_anchor, parse_block_attributes, parse_block_title))),
+        alt((value((), parse_delimited_block), value((), many1(text_line)))),
+    ))(i)
+}
+
+/// 列表项的文字，描述列表项的说明也可以从下一行开始
+fn item_text(i: Span, description: bool) -> IResult<Option<Span>> {
+    let (i, text) = match eol(i) {
+        Ok((rest, _)) if description => opt(preceded(many0(parse_blank_line), recognize(many1(text_line))))(rest)?,
+        _ => map(recognize(pair(parse_line, many0(text_line))), Some)(i)?,
+    };
+    Ok((i, text.map(trim)))
+}
+
+fn parse_list_entry(i: Span<'_>) -> IResult<'_, ListEntry<'_>> {
+    let (i, _) = space0(i)?;
+    let start = i;
+    let (i, (term, marker)) = item_start(i)?;
+    let (i, checked) = match marker.kind {
+        MarkerKind::Unordered => opt(checkbox)(i)?,
+        _ => (i, None),
+    };
+    let (i, text) = item_text(i, marker.kind == MarkerKind::Description)?;
+
+    let last = text.or(term).expect("list items have text and description items have a term");
+    let len = last.location_offset() + last.fragment().len() - start.location_offset();
+    let item_location = location(&start.slice(..len));
+    let (i, attached) = many0(preceded(parse_continuation, attached_block))(i)?;
+    let end = attached.last().map_or(item_location.end, |span| location(span).end);
+    Ok((
+        i,
+        ListEntry {
+            marker,
+            term,
+            checked,
+            text,
+            location: item_location,
+            attached,
+            end,
+        },
+    ))
+}
+
+/// 连续的列表项，各项之间可以有空行
+pub fn parse_list(i: Span<'_>) -> IResult<'_, Vec<ListEntry<'_>>> {
+    many1(terminated(parse_list_entry, many0(parse_blank_line)))(i)
+}
+
+/// 按列表标记组装嵌套的列表，`attach` 负责解析附加的块
+///
+/// 与当前列表标记不同的项开始一个嵌套的列表，除非它的标记属于某个外层列表，
+/// 这时当前列表结束。
+pub fn build_list<'a>(entries: Vec<ListEntry<'a>>, attach: &mut impl FnMut(Span<'a>) -> Vec<Block<'a>>) -> Block<'a> {
+    list(&mut entries.into_iter().peekable(), &mut vec![], attach)
+}
+
+fn list<'a>(
+    entries: &mut Peekable<IntoIter<ListEntry<'a>>>,
+    ancestors: &mut Vec<&'a str>,
+    attach: &mut impl FnMut(Span<'a>) -> Vec<Block<'a>>,
+) -> Block<'a> {
+    let first = entries.peek().expect("a list has at least one item");
+    let marker = first.marker;
+    let mut location = first.location;
+
+    let mut items: Vec<(ListEntry, Vec<Block>)> = vec![];
+    while let Some(entry) = entries.peek() {
+        if entry.marker.key == marker.key {
+            let mut entry = entries.next().expect("peeked");
+            let mut blocks = vec![];
+            for span in take(&mut entry.attached) {
+                blocks.extend(attach(span));
+            }
+            location.end = entry.end;
+            items.push((entry, blocks));
+        } else if ancestors.contains(&entry.marker.key) {
+            break;
+        } else {
+            ancestors.push(marker.key);
+            let nested = list(entries, ancestors, attach);
+            ancestors.pop();
+            location.end = nested.location.end;
+            items.last_mut().expect("the first entry matches").1.push(nested);
+        }
+    }
+
+    let list_items = |items: Vec<(ListEntry<'a>, Vec<Block<'a>>)>| {
+        items
+            .into_iter()
+            .map(|(entry, blocks)| {
+                let text = entry.text.expect("list items always have text");
+                ListItem {
+                    text: text.fragment(),
+                    inlines: parse_inlines(text),
+                    checked: entry.checked,
+                    blocks,
+                    location: entry.location,
+                }
+            })
+            .collect()
+    };
+    let kind = match marker.kind {
+        MarkerKind::Unordered => BlockKind::UnorderedList { items: list_items(items) },
+        MarkerKind::Ordered => BlockKind::OrderedList {
+            start: marker.number.filter(|&number| number != 1),
+            items: list_items(items),
+        },
+        MarkerKind::Description => BlockKind::DescriptionList {
+            items: items
+                .into_iter()
+                .map(|(entry, blocks)| DescriptionItem {
+                    term: entry.term.expect("description items always have a term").fragment(),
+                    text: entry.text.map(|text| *text.fragment()),
+                    inlines: entry.text.map(parse_inlines).unwrap_or_default(),
+                    blocks,
+                    location: entry.location,
+                })
+                .collect(),
+        },
+    };
+    Block {
+        kind,
+        metadata: BlockMetadata::default(),
+        location,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{Block, BlockKind, Position};
+    use crate::parser::list::{build_list, parse_list};
+    use crate::parser::Span;
+
+    /// 列表的简单文本形式，每项一行，嵌套的内容缩进两个空格
+    fn tree(block: &Block, indent: usize, out: &mut String) {
+        let (prefix, items) = match &block.kind {
+            BlockKind::UnorderedList { items } => ("*", items),
+            BlockKind::OrderedList { items, .. } => (".", items),
+            BlockKind::DescriptionList { items } => {
+                for item in items {
+                    let text = item.text.map(|text| format!(" {text}")).unwrap_or_default();
+                    out.push_str(&format!("{}{}::{text}\n", " ".repeat(indent), item.term));
+                    for block in &item.blocks {
+                        tree(block, indent + 2, out);
+                    }
+                }
+                return;
+            }
+            _ => {
+                out.push_str(&format!("{}<block>\n", " ".repeat(indent)));
+                return;
+            }
+        };
+        for item in items {
+            let checkbox = match item.checked {
+                Some(true) => "[x] ",
+                Some(false) => "[ ] ",
+                None => "",
+            };
+            out.push_str(&format!("{}{prefix} {checkbox}{}\n", " ".repeat(indent), item.text));
+            for block in &item.blocks {
+                tree(block, indent + 2, out);
+            }
+        }
+    }
+
+    fn parse(text: &str) -> (String, Block<'_>) {
+        let (i, entries) = parse_list(Span::new(text)).unwrap();
+        assert_eq!(*i.fragment(), "");
+        let block = build_list(entries, &mut |_| vec![]);
+        let mut out = String::new();
+        tree(&block, 0, &mut out);
+        (out, block)
+    }
+
+    #[test]
+    pub fn test_parse_list() {
+        let (out, block) = parse("* one\n** nested\ncontinued\n\n*** deep\n* two\n. step\n.. sub\n* three\n");
+        assert_eq!(
+            out,
+            "* one\n  * nested\ncontinued\n    * deep\n* two\n  . step\n    . sub\n* three\n"
+        );
+        assert_eq!(block.location.start, Position { line: 1, column: 1 });
+        assert_eq!(block.location.end, Position { line: 9, column: 8 });
+    }
+
+    #[test]
+    pub fn test_parse_ordered_list() {
+        let (out, block) = parse("4. four\n5. five\n   - dash\n");
+        assert_eq!(out, ". four\n. five\n  * dash\n");
+        assert!(matches!(block.kind, BlockKind::OrderedList { start: Some(4), .. }));
+    }
+
+    #[test]
+    pub fn test_parse_checklist() {
+        let (out, _) = parse("* [x] done\n* [ ] todo\n* [link] text\n");
+        assert_eq!(out, "* [x] done\n* [ ] todo\n* [link] text\n");
+    }
+
+    #[test]
+    pub fn test_parse_list_continuation() {
+        let text = "* item\n+\n[source]\n----\ncode\n----\n+\nMore text.\n* next\n";
+        let (i, entries) = parse_list(Span::new(text)).unwrap();
+        assert_eq!(*i.fragment(), "");
+
+        let mut attached = vec![];
+        let block = build_list(entries, &mut |span| {
+            attached.push(span.fragment().to_string());
+            vec![]
+        });
+        assert_eq!(attached, vec!["[source]\n----\ncode\n----\n", "More text.\n"]);
+        assert_eq!(block.location.end, Position { line: 9, column: 7 });
+
+        assert!(parse_list(Span::new("*bold* text\n")).is_err());
+        assert!(parse_list(Span::new(".Title\n")).is_err());
+    }
+
+    #[test]
+    pub fn test_parse_description_list() {
+        let (out, block) = parse("CPU:: The brain.\nRAM::\n  Memory\n  that forgets.\nDisk::\n\nSlow.\nSSD::: Faster\n* a list\nTape;; Old\nGPU:: Graphics\n");
+        assert_eq!(
+            out,
+            "CPU:: The brain.\n\
+             RAM:: Memory\n  that forgets.\n\
+             Disk:: Slow.\n\
+             \x20 SSD:: Faster\n\
+             \x20   * a list\n\
+             \x20     Tape:: Old\n\
+             GPU:: Graphics\n"
+        );
+        assert_eq!(block.location.end, Position { line: 11, column: 15 });
+
+        let (out, _) = parse("std::vec::Vec:: A growable array\nEmpty::\nNext:: Item\n");
+        assert_eq!(out, "std::vec::Vec:: A growable array\nEmpty::\nNext:: Item\n");
+
+        assert!(parse_list(Span::new("std::vec::Vec is a type\n")).is_err());
+        assert!(parse_list(Span::new(":: no term\n")).is_err());
+    }
+}
@@ -0,0 +1,222 @@
+use nom::branch::alt;
+use nom::bytes::complete::{is_not, tag, take_till, take_while1};
+use nom::character::complete::{char, satisfy, space0};
+use nom::combinator::{map, opt, recognize, verify};
+use nom::multi::separated_list0;
+use nom::error::{ErrorKind, ParseError};
+use nom::sequence::{delimited, pair, preceded, separated_pair, terminated};
+use nom::Slice;
+
+use crate::ast::BlockMetadata;
+use crate::parser::error::Error;
+use crate::parser::{eol, IResult, Span};
+
+/// 属性列表中的一项
+#[derive(Debug, PartialEq)]
+enum AttrEntry<'a> {
+    Positional { value: &'a str, quoted: bool },
+    Named(&'a str, &'a str),
+}
+
+/// 属性值：单引号或双引号中的文本，或者到下一个逗号为止的文本
+fn attr_value(i: Span<'_>) -> IResult<'_, (&str, bool)> {
+    alt((
+        map(delimited(char('"'), take_till(|c| c == '"'), char('"')), |value: Span| {
+            (*value.fragment(), true)
+        }),
+        map(delimited(char('\''), take_till(|c| c == '\''), char('\'')), |value: Span| {
+            (*value.fragment(), true)
+        }),
+        map(take_till(|c| c == ','), |value: Span| (value.fragment().trim_end(), false)),
+    ))(i)
+}
+
+fn attr_name(i: Span) -> IResult<Span> {
+    take_while1(|c: char| c.is_alphanumeric() || c == '-' || c == '_')(i)
+}
+
+fn attr_entry(i: Span) -> IResult<AttrEntry> {
+    preceded(
+        space0,
+        alt((
+            map(
+                separated_pair(attr_name, delimited(space0, char('='), space0), attr_value),
+                |(name, (value, _))| AttrEntry::Named(name.fragment(), value),
+            ),
+            map(attr_value, |(value, quoted)| AttrEntry::Positional { value, quoted }),
+        )),
+    )(i)
+}
+
+/// 拆开第一个位置属性中的简写：`style#id.role%option`
+fn parse_shorthand<'a>(value: &'a str, metadata: &mut BlockMetadata<'a>) -> &'a str {
+    let mut parts = value.match_indices(['#', '.', '%']).map(|(index, _)| index).peekable();
+    let style = &value[..parts.peek().copied().unwrap_or(value.len())];
+
+    while let Some(start) = parts.next() {
+        let end = parts.peek().copied().unwrap_or(value.len());
+        let part = &value[start + 1..end];
+        if part.is_empty() {
+            continue;
+        }
+        match &value[start..start + 1] {
+            "#" => metadata.id = Some(part),
+            "." => metadata.roles.push(part),
+            _ => metadata.options.push(part),
+        }
+    }
+    style
+}
+
+/// 解析方括号中的属性列表
+pub fn parse_attrlist(i: Span) -> IResult<BlockMetadata> {
+    let (i, entries) = separated_list0(char(','), attr_entry)(i)?;
+
+    let mut metadata = BlockMetadata::default();
+    for (index, entry) in entries.into_iter().enumerate() {
+        match entry {
+            AttrEntry::Positional { value, quoted: false } if index == 0 => {
+                let style = parse_shorthand(value, &mut metadata);
+                metadata.positional.push(style);
+            }
+            AttrEntry::Positional { value, .. } => metadata.positional.push(value),
+            AttrEntry::Named("id", value) => metadata.id = Some(value),
+            AttrEntry::Named("role", value) => metadata.roles.extend(value.split_whitespace()),
+            AttrEntry::Named("opts" | "options", value) => {
+                metadata.options.extend(value.split(',').map(str::trim).filter(|option| !option.is_empty()));
+            }
+            AttrEntry::Named(name, value) => metadata.named.push((name, value)),
+        }
+    }
+    // `[]` 和 `[,rust]` 中的空位置属性只用来占位
+    if metadata.positional.iter().all(|value| value.is_empty()) {
+        metadata.positional.clear();
+    }
+    Ok((i, metadata))
+}
+
+/// 块属性行：`[source,rust]`、`[#id.role]`
+pub fn parse_block_attributes(i: Span) -> IResult<BlockMetadata> {
+    let (i, line) = terminated(is_not("\r\n"), eol)(i)?;
+    let text = line.fragment().trim_end();
+    let content = text
+        .strip_prefix('[')
+        .and_then(|text| text.strip_suffix(']'))
+        .filter(|content| {
+            content.is_empty() || content.starts_with(|c: char| c.is_alphanumeric() || "_.#%{,\"'".contains(c))
+        });
+
+    match content {
+        Some(content) => {
+            let (_, metadata) = parse_attrlist(line.slice(1..content.len() + 1))?;
+            Ok((i, metadata))
+        }
+        None => Err(nom::Err::Error(Error::from_error_kind(line, ErrorKind::Verify))),
+    }
+}
+
+/// 块锚点行：`[[id]]` 或 `[[id,reftext]]`
+pub fn parse_block_anchor(i: Span) -> IResult<BlockMetadata> {
+    let id = recognize(pair(
+        satisfy(|c| c.is_alphabetic() || c == '_' || c == ':'),
+        opt(is_not(",]\r\n")),
+    ));
+    let reftext = preceded(pair(char(','), space0), is_not("]\r\n"));
+    map(
+        terminated(
+            delimited(tag("[["), pair(id, opt(reftext)), tag("]]")),
+            pair(space0, eol),
+        ),
+        |(id, reftext)| BlockMetadata {
+            id: Some(id.fragment()),
+            named: reftext.map(|reftext| vec![("reftext", *reftext.fragment())]).unwrap_or_default(),
+            ..BlockMetadata::default()
+        },
+    )(i)
+}
+
+/// 块标题行：`.Title`，点号之后不能是空白或另一个点号
+pub fn parse_block_title(i: Span) -> IResult<BlockMetadata> {
+    map(
+        terminated(
+            preceded(
+                char('.'),
+                verify(is_not("\r\n"), |title: &Span| {
+                    !title.starts_with(|c: char| c.is_whitespace() || c == '.')
+                }),
+            ),
+            eol,
+        ),
+        |title| BlockMetadata {
+            title: Some(title.fragment().trim_end()),
+            ..BlockMetadata::default()
+        },
+    )(i)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::BlockMetadata;
+    use crate::parser::metadata::{parse_attrlist, parse_block_anchor, parse_block_attributes, parse_block_title};
+    use crate::parser::Span;
+
+    #[test]
+    pub fn test_parse_attrlist() {
+        let (_, metadata) = parse_attrlist(Span::new("source, rust,linenums")).unwrap();
+        assert_eq!(metadata.positional, vec!["source", "rust", "linenums"]);
+        assert_eq!(metadata.style(), Some("source"));
+
+        let (_, metadata) = parse_attrlist(Span::new("quote#id.role1.role2%opt, \"Author, A.\", cite=Book")).unwrap();
+        assert_eq!(
+            metadata,
+            BlockMetadata {
+                id: Some("id"),
+                title: None,
+                roles: vec!["role1", "role2"],
+                options: vec!["opt"],
+                positional: vec!["quote", "Author, A."],
+                named: vec![("cite", "Book")],
+            }
+        );
+
+        let (_, metadata) = parse_attrlist(Span::new("#top, role=\"a b\", opts=\"header,footer\"")).unwrap();
+        assert_eq!(metadata.id, Some("top"));
+        assert_eq!(metadata.roles, vec!["a", "b"]);
+        assert_eq!(metadata.options, vec!["header", "footer"]);
+        assert_eq!(metadata.style(), None);
+    }
+
+    #[test]
+    pub fn test_parse_block_attributes() {
+        let (i, metadata) = parse_block_attributes(Span::new("[.lead]\nText")).unwrap();
+        assert_eq!(*i.fragment(), "Text");
+        assert_eq!(metadata.roles, vec!["lead"]);
+
+        let (_, metadata) = parse_block_attributes(Span::new("[]\n")).unwrap();
+        assert_eq!(metadata, BlockMetadata::default());
+
+        assert!(parse_block_attributes(Span::new("[ not a list]\n")).is_err());
+        assert!(parse_block_attributes(Span::new("[link] text\n")).is_err());
+    }
+
+    #[test]
+    pub fn test_parse_block_anchor() {
+        let (_, metadata) = parse_block_anchor(Span::new("[[intro]]\n")).unwrap();
+        assert_eq!(metadata.id, Some("intro"));
+
+        let (_, metadata) = parse_block_anchor(Span::new("[[intro, Introduction]]\n")).unwrap();
+        assert_eq!(metadata.id, Some("intro"));
+        assert_eq!(metadata.attribute("reftext"), Some("Introduction"));
+
+        assert!(parse_block_anchor(Span::new("[[1st]]\n")).is_err());
+    }
+
+    #[test]
+    pub fn test_parse_block_title() {
+        let (_, metadata) = parse_block_title(Span::new(".Block title \n")).unwrap();
+        assert_eq!(metadata.title, Some("Block title"));
+
+        assert!(parse_block_title(Span::new(". not a title\n")).is_err());
+        assert!(parse_block_title(Span::new("....\n")).is_err());
+    }
+}
@@ -0,0 +1,831 @@
+use std::mem::take;
+
+use nom::branch::alt;
+use nom::character::complete::{alpha1, char, line_ending, space1};
+use nom::combinator::{consumed, eof, map, map_opt, value};
+use nom::error::context;
+use nom::multi::many0;
+use nom::sequence::{pair, preceded, terminated};
+use nom::Slice;
+use nom_locate::LocatedSpan;
+
+use crate::ast::{AdmonitionKind, Attribute, Block, BlockKind, BlockMetadata, Document, Location, Position, Title};
+use crate::diagnostic::{Diagnostic, Severity};
+use crate::preprocessor::{self, preprocess, Output};
+use block::{
+    parse_blank_line, parse_block, parse_comment_block, parse_comment_line, parse_delimited_block,
+    parse_section_title, DelimitedBlock, Delimiter,
+};
+use callout::{parse_callout_list, parse_callouts};
+use header::{parse_doc_attr, parse_doc_header};
+use error::{Error, ParseError};
+use inline::parse_inlines;
+use list::{build_list, parse_list, ListEntry};
+use metadata::{parse_block_anchor, parse_block_attributes, parse_block_title};
+use reference::resolve_references;
+use section::{build_sections, Node};
+use table::parse_table;
+
+pub mod block;
+pub mod callout;
+pub mod error;
+pub mod header;
+pub mod inline;
+pub mod list;
+pub mod metadata;
+pub mod reference;
+pub mod section;
+pub mod table;
+
+/// 带有行列信息的输入
+pub type Span<'a> = LocatedSpan<&'a str>;
+
+/// 使用 [`Error`] 作为错误类型的解析结果
+pub type IResult<'a, O> = nom::IResult<Span<'a>, O, Error<'a>>;
+
+/// 匹配行尾或输入结束
+pub(crate) fn eol(i: Span) -> IResult<Span> {
+    alt((line_ending, eof))(i)
+}
+
+/// `span` 的起始位置
+pub(crate) fn position(span: &Span) -> Position {
+    Position {
+        line: span.location_line() as usize,
+        column: span.get_utf8_column(),
+    }
+}
+
+/// `span` 覆盖的范围，不包括末尾的换行符
+pub(crate) fn location(span: &Span) -> Location {
+    Location::covering(position(span), span.fragment().trim_end_matches(['\r', '\n']))
+}
+
+/// 去掉 `span` 首尾的空白
+pub(crate) fn trim(span: Span) -> Span {
+    let text = span.fragment();
+    let start = text.len() - text.trim_start().len();
+    span.slice(start..text.trim_end().len().max(start))
+}
+
+/// 计算 `fragment` 在 `input` 中覆盖的范围，`fragment` 必须是 `input` 的切片
+pub(crate) fn locate(input: &str, fragment: &str) -> Location {
+    let offset = (fragment.as_ptr() as usize).saturating_sub(input.as_ptr() as usize);
+    // 不是 `input` 的切片时（例如替换过属性引用的文本）不会越界或切开字符
+    let before = input.get(..offset.min(input.len())).unwrap_or("");
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    let start = Position {
+        line: before.matches('\n').count() + 1,
+        column: before[line_start..].chars().count() + 1,
+    };
+    Location::covering(start, fragment)
+}
+
+/// 记录错误，并跳过出错位置所在行的剩余部分，从下一行继续解析
+fn recover<'a>(input: Span<'a>, err: nom::Err<Error<'a>>, diagnostics: &mut Vec<Diagnostic>) -> Span<'a> {
+    match err {
+        nom::Err::Error(err) | nom::Err::Failure(err) => {
+            diagnostics.push(err.to_diagnostic());
+            let span = input.slice(err.span.location_offset()..);
+            let len = span.fragment().find('\n').map_or(span.fragment().len(), |index| index + 1);
+            span.slice(len..)
+        }
+        nom::Err::Incomplete(_) => unreachable!("complete parsers never return Incomplete"),
+    }
+}
+
+/// 文档主体中的一个元素，注释和空行不会产生元素
+enum Element<'a> {
+    Title(Title<'a>),
+    Block(Block<'a>),
+    Delimited(DelimitedBlock<'a>),
+    List(Vec<ListEntry<'a>>),
+    Metadata(BlockMetadata<'a>),
+    Attribute(Attribute<'a>),
+    Warning(Diagnostic),
+    Skip,
+}
+
+fn parse_paragraph(i: Span<'_>) -> IResult<'_, Block<'_>> {
+    let (i, (span, _)) = consumed(parse_block)(i)?;
+    let span = span.slice(..span.fragment().trim_end().len());
+    Ok((
+        i,
+        Block {
+            kind: BlockKind::Paragraph {
+                lines: span.fragment(),
+                inlines: parse_inlines(span),
+            },
+            metadata: BlockMetadata::default(),
+            location: location(&span),
+        },
+    ))
+}
+
+/// `NOTE: 文字` 形式的提示段落
+fn parse_admonition_paragraph(i: Span<'_>) -> IResult<'_, Block<'_>> {
+    let start = i;
+    let (i, kind) = terminated(
+        map_opt(alpha1, |label: Span| AdmonitionKind::from_label(label.fragment())),
+        pair(char(':'), space1),
+    )(i)?;
+    let (i, paragraph) = parse_paragraph(i)?;
+    let location = Location {
+        start: position(&start),
+        end: paragraph.location.end,
+    };
+    Ok((
+        i,
+        Block {
+            kind: BlockKind::Admonition {
+                kind,
+                blocks: vec![paragraph],
+                paragraph: true,
+            },
+            metadata: BlockMetadata::default(),
+            location,
+        },
+    ))
+}
+
+/// 带有 `[NOTE]` 等样式的段落和 `====` 块是提示块
+fn admonition_style(block: Block<'_>) -> Block<'_> {
+    let Some(kind) = block.metadata.style().and_then(AdmonitionKind::from_label) else {
+        return block;
+    };
+    let Block { kind: inner, metadata, location } = block;
+    let (blocks, paragraph) = match inner {
+        BlockKind::Example { blocks } => (blocks, false),
+        paragraph @ BlockKind::Paragraph { .. } => {
+            let paragraph = Block {
+                kind: paragraph,
+                metadata: BlockMetadata::default(),
+                location,
+            };
+            (vec![paragraph], true)
+        }
+        inner => {
+            return Block {
+                kind: inner,
+                metadata,
+                location,
+            }
+        }
+    };
+    Block {
+        kind: BlockKind::Admonition {
+            kind,
+            blocks,
+            paragraph,
+        },
+        metadata,
+        location,
+    }
+}
+
+/// 解析一个元素，`sections` 为 `false` 时标题行按段落处理
+fn parse_element(i: Span<'_>, sections: bool) -> IResult<'_, Element<'_>> {
+    if sections {
+        if let Ok((i, title)) = parse_section_title(i) {
+            return Ok((i, Element::Title(title)));
+        }
+    }
+    alt((
+        map(parse_blank_line, |_| Element::Skip),
+        map(parse_delimited_block, |block| match block.delimiter {
+            Delimiter::Comment => block.warning.map_or(Element::Skip, Element::Warning),
+            _ => Element::Delimited(block),
+        }),
+        map(terminated(parse_comment_line, eol), |_| Element::Skip),
+        map(
+            terminated(parse_doc_attr, eol),
+            Element::Attribute,
+        ),
+        map(
+            alt((parse_block_anchor, parse_block_attributes, parse_block_title)),
+            Element::Metadata,
+        ),
+        map(parse_admonition_paragraph, Element::Block),
+        map(parse_list, Element::List),
+        map(parse_callout_list, Element::Block),
+        map(parse_paragraph, Element::Block),
+    ))(i)
+}
+
+/// 解析文档主体时收集的属性和诊断
+struct Body<'a> {
+    input: Span<'a>,
+    attrs: Vec<Attribute<'a>>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> Body<'a> {
+    /// 解析 `rest` 中的全部元素，出错后从下一行继续
+    ///
+    /// 元数据行会累积起来，附加到之后的第一个块或章节上。
+    fn nodes(&mut self, mut rest: Span<'a>, sections: bool) -> Vec<Node<'a>> {
+        let mut nodes = vec![];
+        let mut metadata = BlockMetadata::default();
+        while !rest.fragment().is_empty() {
+            let element = match parse_element(rest, sections) {
+                Ok((remaining, element)) => {
+                    rest = remaining;
+                    element
+                }
+                Err(err) => {
+                    rest = recover(self.input, err, &mut self.diagnostics);
+                    continue;
+                }
+            };
+            match element {
+                Element::Title(title) => nodes.push(Node::Title(title, take(&mut metadata))),
+                Element::Block(mut block) => {
+                    block.metadata = take(&mut metadata);
+                    nodes.push(Node::Block(admonition_style(block)));
+                }
+                Element::Delimited(block) => {
+                    let metadata = take(&mut metadata);
+                    let block = self.delimited(block, metadata);
+                    nodes.push(Node::Block(admonition_style(block)));
+                }
+                Element::List(entries) => {
+                    let mut block = build_list(entries, &mut |span| self.blocks(span));
+                    block.metadata = take(&mut metadata);
+                    nodes.push(Node::Block(block));
+                }
+                Element::Metadata(more) => metadata.merge(more),
+                Element::Attribute(attr) => self.attrs.push(attr),
+                Element::Warning(warning) => self.diagnostics.push(warning),
+                Element::Skip => {}
+            }
+        }
+        nodes
+    }
+
+    /// 复合块中的内容按块解析，不能包含章节
+    fn blocks(&mut self, content: Span<'a>) -> Vec<Block<'a>> {
+        let nodes = self.nodes(content, false);
+        let (blocks, warnings) = build_sections(nodes);
+        self.diagnostics.extend(warnings);
+        blocks
+    }
+
+    fn delimited(&mut self, block: DelimitedBlock<'a>, metadata: BlockMetadata<'a>) -> Block<'a> {
+        self.diagnostics.extend(block.warning);
+        let content = block.content;
+        let kind = match block.delimiter {
+            Delimiter::Listing => BlockKind::Listing {
+                content: content.fragment(),
+                callouts: parse_callouts(content),
+            },
+            Delimiter::Literal => BlockKind::Literal { content: content.fragment() },
+            Delimiter::Passthrough => BlockKind::Passthrough { content: content.fragment() },
+            Delimiter::Example => BlockKind::Example { blocks: self.blocks(content) },
+            Delimiter::Sidebar => BlockKind::Sidebar { blocks: self.blocks(content) },
+            Delimiter::Quote => BlockKind::Quote { blocks: self.blocks(content) },
+            Delimiter::Open => BlockKind::Open { blocks: self.blocks(content) },
+            Delimiter::Table(delimiter) => {
+                let (table, warnings) = parse_table(content, delimiter, &metadata, &mut |span| self.blocks(span));
+                self.diagnostics.extend(warnings);
+                BlockKind::Table(table)
+            }
+            Delimiter::Comment => unreachable!("comment blocks are skipped by parse_element"),
+        };
+        Block {
+            kind,
+            metadata,
+            location: block.location,
+        }
+    }
+}
+
+/// 解析完整的 AsciiDoc 文档
+///
+/// 遇到错误时跳过出错的行继续解析，以便一次报告尽可能多的错误；
+/// 只要出现错误就返回 [`ParseError`]，其中包含全部诊断。
+pub fn parse_document(i: &str) -> Result<Document<'_>, ParseError> {
+    parse_document_with(i, &[])
+}
+
+/// 和 [`parse_document`] 相同，`overrides` 是命令行等处给出的覆盖属性，
+/// 生成章节 id 和替换标题中的属性引用时优先于文档中的属性条目
+pub fn parse_document_with<'a>(i: &'a str, overrides: &[Attribute]) -> Result<Document<'a>, ParseError> {
+    let input = Span::new(i);
+    let mut body = Body {
+        input,
+        attrs: vec![],
+        diagnostics: vec![],
+    };
+
+    // 未闭合的注释块会一直延续到文档末尾，这时没有头部，警告在解析主体时产生
+    let prelude = many0(alt((
+        parse_blank_line,
+        value((), parse_comment_block),
+        terminated(parse_comment_line, eol),
+    )));
+    let (rest, header) = match preceded(prelude, context("the document header", parse_doc_header))(input) {
+        Ok((rest, header)) => (rest, Some(header)),
+        Err(nom::Err::Error(_)) => (input, None),
+        Err(err) => (recover(input, err, &mut body.diagnostics), None),
+    };
+
+    let nodes = body.nodes(rest, true);
+    let (mut blocks, warnings) = build_sections(nodes);
+    let attrs: Vec<&Attribute> = header.iter().flat_map(|header| header.attrs.iter()).chain(&body.attrs).collect();
+    let (references, reference_warnings) = resolve_references(i, &mut blocks, &attrs, overrides);
+    let mut diagnostics = body.diagnostics;
+    diagnostics.extend(warnings);
+    diagnostics.extend(reference_warnings);
+    diagnostics.sort_by_key(|diagnostic| diagnostic.location.start);
+
+    if diagnostics.iter().any(|diagnostic| diagnostic.severity == Severity::Error) {
+        return Err(ParseError { diagnostics });
+    }
+    Ok(Document {
+        header,
+        blocks,
+        attrs: body.attrs,
+        warnings: diagnostics,
+        source: i,
+        references,
+    })
+}
+
+/// 读取文件时的入口：按 `options` 中的安全模式和读取器预处理 `source`，再用其中的覆盖属性解析
+///
+/// 预处理结果存入 `output`，文档树借用其中展开后的文本；返回的引用用于报告预处理的警告和
+/// 换算转换时的诊断。文档和错误中的诊断已经换算为源文件中的位置。
+pub fn load_document<'a>(
+    source: &str,
+    options: &preprocessor::Options,
+    output: &'a mut Output,
+) -> (&'a Output, Result<Document<'a>, ParseError>) {
+    *output = preprocess(source, options);
+    let output = &*output;
+    let remap = |diagnostics: &mut Vec<Diagnostic>| {
+        for diagnostic in diagnostics {
+            *diagnostic = output.remap(diagnostic);
+        }
+    };
+    let result = match parse_document_with(&output.source, &options.attributes) {
+        Ok(mut doc) => {
+            remap(&mut doc.warnings);
+            Ok(doc)
+        }
+        Err(mut err) => {
+            remap(&mut err.diagnostics);
+            Err(err)
+        }
+    };
+    (output, result)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use crate::ast::{AdmonitionKind, Attribute, AuthorInfo, BlockKind, InlineKind, Location, Name, Position};
+    use crate::diagnostic::Severity;
+    use crate::parser::{load_document, parse_document, parse_document_with};
+    use crate::preprocessor::{Options, Output};
+    use crate::safe_mode::SafeMode;
+
+    #[test]
+    pub fn test_parse_document() {
+        let text = include_str!("../../readme.adoc");
+        let doc = parse_document(text).unwrap();
+
+        let header = doc.header.unwrap();
+        assert_eq!(header.title, "Rusciidoc");
+        assert_eq!(
+            header.auth_info,
+            vec![AuthorInfo {
+                author: Name {
+                    firstname: "Heng",
+                    middle_name: Some("Yue"),
+                    lastname: Some("Wang"),
+                },
+                email: Some("admin@eastack.me"),
+            }]
+        );
+        assert_eq!(header.attrs.len(), 2);
+        assert_eq!(doc.blocks.len(), 1);
+        assert!(matches!(
+            doc.blocks[0].kind,
+            BlockKind::Paragraph { lines: "The AsciiDoc implementation for Rust.", .. }
+        ));
+        assert_eq!(
+            doc.blocks[0].location,
+            Location {
+                start: Position { line: 6, column: 1 },
+                end: Position { line: 6, column: 38 },
+            }
+        );
+    }
+
+    #[test]
+    pub fn test_parse_document_body() {
+        let text = "// leading comment\n\
+                    = Title\n\
+                    \n\
+                    Preamble.\n\
+                    \n\
+                    == Section\n\
+                    :sectattr: value\n\
+                    ////\n\
+                    hidden\n\
+                    ////\n\
+                    First line.\n\
+                    Second line.\n";
+        let doc = parse_document(text).unwrap();
+
+        assert_eq!(doc.header.unwrap().title, "Title");
+        assert_eq!(doc.attrs.len(), 1);
+        assert_eq!(doc.attrs[0].location.start, Position { line: 7, column: 1 });
+        assert_eq!(doc.blocks.len(), 2);
+        assert!(matches!(doc.blocks[0].kind, BlockKind::Paragraph { lines: "Preamble.", .. }));
+
+        let BlockKind::Section(section) = &doc.blocks[1].kind else {
+            panic!("expected section, got {:?}", doc.blocks[1]);
+        };
+        assert_eq!(section.level, 2);
+        assert_eq!(section.title, "Section");
+        assert_eq!(
+            doc.blocks[1].location,
+            Location {
+                start: Position { line: 6, column: 1 },
+                end: Position { line: 12, column: 13 },
+            }
+        );
+
+        let BlockKind::Paragraph { lines, inlines } = &section.blocks[0].kind else {
+            panic!("expected paragraph, got {:?}", section.blocks[0]);
+        };
+        assert_eq!(*lines, "First line.\nSecond line.");
+        assert_eq!(inlines[0].location.end, Position { line: 12, column: 13 });
+    }
+
+    #[test]
+    pub fn test_parse_document_errors() {
+        let text = "= Title\n\
+                    Ann Author <ann@example.com\n\
+                    \n\
+                    Some text.\n\
+                    \n\
+                    ////\n\
+                    unterminated\n";
+        let err = parse_document(text).unwrap_err();
+
+        let messages: Vec<&str> = err.diagnostics.iter().map(|d| d.message.as_str()).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "expected `>` to close the email address",
+                "unterminated comment block",
+            ]
+        );
+        assert_eq!(err.diagnostics[0].location.start, Position { line: 2, column: 28 });
+        assert_eq!(
+            err.diagnostics[0].notes,
+            vec![
+                "while parsing the author line starting at line 2",
+                "while parsing the document header starting at line 1",
+            ]
+        );
+        assert_eq!(err.diagnostics[1].severity, Severity::Warning);
+        assert_eq!(err.diagnostics[1].location.start, Position { line: 6, column: 1 });
+    }
+
+    #[test]
+    pub fn test_parse_document_unterminated_comment() {
+        let doc = parse_document("= Title\n\nIntro.\n\n////\nhidden\n\n== Hidden\n").unwrap();
+        assert_eq!(doc.blocks.len(), 1);
+        assert_eq!(doc.warnings.len(), 1);
+        assert_eq!(doc.warnings[0].message, "unterminated comment block");
+        assert_eq!(doc.warnings[0].location.start.line, 5);
+    }
+
+    #[test]
+    pub fn test_parse_delimited_blocks() {
+        let text = "Intro.\n\
+                    ****\n\
+                    Sidebar.\n\
+                    \n\
+                    ====\n\
+                    == Not a section\n\
+                    ====\n\
+                    ****\n\
+                    \n\
+                    ----\n\
+                    *raw*\n\
+                    \n\
+                    ----\n";
+        let doc = parse_document(text).unwrap();
+        assert_eq!(doc.blocks.len(), 3);
+
+        let BlockKind::Sidebar { blocks } = &doc.blocks[1].kind else {
+            panic!("expected sidebar, got {:?}", doc.blocks[1]);
+        };
+        assert_eq!(doc.blocks[1].location.start, Position { line: 2, column: 1 });
+        assert_eq!(doc.blocks[1].location.end, Position { line: 8, column: 5 });
+        assert!(matches!(blocks[0].kind, BlockKind::Paragraph { lines: "Sidebar.", .. }));
+
+        let BlockKind::Example { blocks } = &blocks[1].kind else {
+            panic!("expected example, got {:?}", blocks[1]);
+        };
+        assert!(matches!(blocks[0].kind, BlockKind::Paragraph { lines: "== Not a section", .. }));
+        assert_eq!(blocks[0].location.start, Position { line: 6, column: 1 });
+
+        assert_eq!(
+            doc.blocks[2].kind,
+            BlockKind::Listing {
+                content: "*raw*\n",
+                callouts: vec![],
+            }
+        );
+    }
+
+    #[test]
+    pub fn test_parse_block_metadata() {
+        let text = "[[first]]\n\
+                    [.lead]\n\
+                    Lead paragraph.\n\
+                    \n\
+                    .Example code\n\
+                    [source,rust]\n\
+                    \n\
+                    ----\n\
+                    fn main() {}\n\
+                    ----\n\
+                    \n\
+                    [#install]\n\
+                    == Install\n";
+        let doc = parse_document(text).unwrap();
+
+        let metadata = &doc.blocks[0].metadata;
+        assert_eq!(metadata.id, Some("first"));
+        assert_eq!(metadata.roles, vec!["lead"]);
+
+        let metadata = &doc.blocks[1].metadata;
+        assert_eq!(metadata.title, Some("Example code"));
+        assert_eq!(metadata.positional, vec!["source", "rust"]);
+        assert_eq!(doc.blocks[1].location.start, Position { line: 8, column: 1 });
+
+        assert!(matches!(doc.blocks[2].kind, BlockKind::Section(_)));
+        assert_eq!(doc.blocks[2].metadata.id, Some("install"));
+    }
+
+    #[test]
+    pub fn test_parse_lists() {
+        let text = "Intro.\n\
+                    \n\
+                    .Steps\n\
+                    . First\n\
+                    +\n\
+                    ====\n\
+                    * inside\n\
+                    ====\n\
+                    . Second\n\
+                    \n\
+                    After.\n";
+        let doc = parse_document(text).unwrap();
+        assert_eq!(doc.blocks.len(), 3);
+        assert_eq!(doc.blocks[1].metadata.title, Some("Steps"));
+        assert_eq!(doc.blocks[1].location.start, Position { line: 4, column: 1 });
+        assert_eq!(doc.blocks[1].location.end, Position { line: 9, column: 9 });
+
+        let BlockKind::OrderedList { start: None, items } = &doc.blocks[1].kind else {
+            panic!("expected ordered list, got {:?}", doc.blocks[1]);
+        };
+        assert_eq!(items.len(), 2);
+        let BlockKind::Example { blocks } = &items[0].blocks[0].kind else {
+            panic!("expected example, got {:?}", items[0].blocks);
+        };
+        assert!(matches!(blocks[0].kind, BlockKind::UnorderedList { .. }));
+        assert!(matches!(doc.blocks[2].kind, BlockKind::Paragraph { lines: "After.", .. }));
+    }
+
+    #[test]
+    pub fn test_parse_tables() {
+        let text = "|===\n\
+                    a|\n\
+                    Nested:\n\
+                    \n\
+                    !===\n\
+                    !x !y\n\
+                    !===\n\
+                    |===\n\
+                    \n\
+                    ,===\n\
+                    a,b\n\
+                    ,===\n";
+        let doc = parse_document(text).unwrap();
+        assert_eq!(doc.blocks.len(), 2);
+
+        let BlockKind::Table(table) = &doc.blocks[0].kind else {
+            panic!("expected table, got {:?}", doc.blocks[0]);
+        };
+        assert_eq!(table.columns.len(), 1);
+        let blocks = &table.rows[0][0].blocks;
+        assert!(matches!(blocks[0].kind, BlockKind::Paragraph { lines: "Nested:", .. }));
+        let BlockKind::Table(nested) = &blocks[1].kind else {
+            panic!("expected nested table, got {:?}", blocks[1]);
+        };
+        assert_eq!(nested.rows[0].len(), 2);
+        assert_eq!(blocks[1].location.start, Position { line: 5, column: 1 });
+
+        let BlockKind::Table(csv) = &doc.blocks[1].kind else {
+            panic!("expected table, got {:?}", doc.blocks[1]);
+        };
+        assert_eq!(csv.rows[0][1].text, "b");
+    }
+
+    #[test]
+    pub fn test_parse_admonitions() {
+        let text = "WARNING: Back up\nfirst.\n\
+                    \n\
+                    [TIP]\n\
+                    Styled paragraph.\n\
+                    \n\
+                    [CAUTION]\n\
+                    ====\n\
+                    Inside.\n\
+                    ====\n\
+                    \n\
+                    NOTE:missing space\n";
+        let doc = parse_document(text).unwrap();
+        assert_eq!(doc.blocks.len(), 4);
+
+        let BlockKind::Admonition { kind, blocks, paragraph } = &doc.blocks[0].kind else {
+            panic!("expected admonition, got {:?}", doc.blocks[0]);
+        };
+        assert_eq!((*kind, *paragraph), (AdmonitionKind::Warning, true));
+        assert!(matches!(blocks[0].kind, BlockKind::Paragraph { lines: "Back up\nfirst.", .. }));
+        assert_eq!(blocks[0].location.start, Position { line: 1, column: 10 });
+        assert_eq!(doc.blocks[0].location.start, Position { line: 1, column: 1 });
+
+        assert!(matches!(
+            doc.blocks[1].kind,
+            BlockKind::Admonition { kind: AdmonitionKind::Tip, paragraph: true, .. }
+        ));
+        assert!(matches!(
+            doc.blocks[2].kind,
+            BlockKind::Admonition { kind: AdmonitionKind::Caution, paragraph: false, .. }
+        ));
+        assert!(matches!(doc.blocks[3].kind, BlockKind::Paragraph { .. }));
+    }
+
+    #[test]
+    pub fn test_parse_references() {
+        let text = "= Doc\n:product: Rusciidoc\n\n\
+                    == Getting Started\n\n\
+                    See <<_getting_started_2>> and <<custom,Custom>>.\n\n\
+                    == Getting Started\n\n\
+                    [[custom]]\n.Custom block\n====\nInside.\n====\n\n\
+                    :idprefix:\n:idseparator: -\n\n\
+                    == About {product}\n\n\
+                    * Links to <<missing>>.\n\n\
+                    :!sectids:\n\n\
+                    == No Id\n";
+        let doc = parse_document(text).unwrap();
+
+        let ids: Vec<_> = doc
+            .blocks
+            .iter()
+            .filter_map(|block| match &block.kind {
+                BlockKind::Section(section) => Some(section.id.as_deref()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(ids, [Some("_getting_started"), Some("_getting_started_2"), Some("about-rusciidoc"), None]);
+        assert_eq!(doc.references.get("custom"), Some(&Some("Custom block".to_string())));
+
+        let BlockKind::Section(section) = &doc.blocks[0].kind else {
+            panic!("expected section, got {:?}", doc.blocks[0]);
+        };
+        let BlockKind::Paragraph { inlines, .. } = &section.blocks[0].kind else {
+            panic!("expected paragraph, got {:?}", section.blocks[0]);
+        };
+        let texts: Vec<_> = inlines
+            .iter()
+            .filter_map(|inline| match &inline.kind {
+                InlineKind::Xref(xref) => Some(xref.text.as_deref()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts, [Some("Getting Started"), Some("Custom")]);
+
+        assert_eq!(doc.warnings.len(), 1);
+        assert_eq!(doc.warnings[0].message, "possible invalid reference: missing");
+        assert_eq!(doc.warnings[0].location.start, Position { line: 21, column: 12 });
+
+        let doc = parse_document("[[a]]\nOne.\n\n[[a]]\nTwo.\n").unwrap();
+        assert_eq!(doc.warnings[0].message, "id already in use: a");
+        assert_eq!(doc.warnings[0].location.start, Position { line: 5, column: 1 });
+    }
+
+    #[test]
+    pub fn test_parse_references_in_titles_and_cells() {
+        let text = "\
+== See <<missing>>
+
+.Also <<gone>>
+|===
+|Head <<target>>
+
+|a <<nowhere>>
+|b
+
+second <<lost>>
+a|<<inner>>
+l|<<literal>>
+|===
+
+[[target]]
+Target.
+
+[%header,format=csv]
+|===
+Name,Ref
+\"x, <<quoted>>\",\"\"\"y\"\" <<escaped>>\"
+|===
+";
+        let doc = parse_document(text).unwrap();
+        let warnings: Vec<_> = doc
+            .warnings
+            .iter()
+            .map(|warning| (warning.message.as_str(), warning.location.start))
+            .collect();
+        let at = |line, column| Position { line, column };
+        assert_eq!(
+            warnings,
+            [
+                ("possible invalid reference: missing", at(1, 8)),
+                ("possible invalid reference: gone", at(3, 7)),
+                ("possible invalid reference: nowhere", at(7, 4)),
+                ("possible invalid reference: lost", at(10, 8)),
+                ("possible invalid reference: inner", at(11, 3)),
+                ("possible invalid reference: quoted", at(21, 5)),
+                // 还原过转义的值不是文档的切片，警告指向整个单元格
+                ("possible invalid reference: escaped", at(21, 18)),
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_parse_references_with_overrides() {
+        let text = ":idprefix: doc-\n:product: Doc\n\n== About {product}\n\n[[tool]]\n== Using {product}\n\n<<tool>>\n";
+        let overrides = [
+            Attribute::from_override("idprefix=sec-"),
+            Attribute::from_override("idseparator=-"),
+            Attribute::from_override("product=Rusciidoc"),
+        ];
+        let doc = parse_document_with(text, &overrides).unwrap();
+        let BlockKind::Section(section) = &doc.blocks[0].kind else {
+            panic!("expected section, got {:?}", doc.blocks[0]);
+        };
+        assert_eq!(section.id.as_deref(), Some("sec-about-rusciidoc"));
+        assert_eq!(doc.references.get("tool"), Some(&Some("Using Rusciidoc".to_string())));
+
+        let BlockKind::Section(section) = &doc.blocks[1].kind else {
+            panic!("expected section, got {:?}", doc.blocks[1]);
+        };
+        let BlockKind::Paragraph { inlines, .. } = &section.blocks[0].kind else {
+            panic!("expected paragraph, got {:?}", section.blocks[0]);
+        };
+        let InlineKind::Xref(xref) = &inlines[0].kind else {
+            panic!("expected xref, got {:?}", inlines[0]);
+        };
+        assert_eq!(xref.text.as_deref(), Some("Using Rusciidoc"));
+
+        let doc = parse_document_with(text, &[Attribute::from_override("sectids!")]).unwrap();
+        assert!(matches!(&doc.blocks[0].kind, BlockKind::Section(section) if section.id.is_none()));
+    }
+
+    #[test]
+    pub fn test_load_document() {
+        let resolver: HashMap<String, String> = [
+            ("part.adoc".to_string(), "== Part\n\n////\nunterminated\n".to_string()),
+        ]
+        .into();
+        let options = Options {
+            resolver: Some(&resolver),
+            path: Some("main.adoc"),
+            attributes: vec![Attribute::from_override("idprefix=id_")],
+            safe_mode: SafeMode::Safe,
+            ..Options::default()
+        };
+        let source = "= Doc\n\ninclude::../secret.adoc[]\n\ninclude::part.adoc[]\n";
+        let mut output = Output::default();
+        let (preprocessed, doc) = load_document(source, &options, &mut output);
+        let doc = doc.unwrap();
+
+        let warnings: Vec<_> = preprocessed.warnings.iter().map(|warning| warning.message.as_str()).collect();
+        assert_eq!(warnings, ["include file is outside the base directory: ../secret.adoc"]);
+        assert!(matches!(&doc.blocks[1].kind, BlockKind::Section(section) if section.id.as_deref() == Some("id_part")));
+
+        // 解析的诊断指向被包含文件中的行
+        assert_eq!(doc.warnings[0].file.as_deref(), Some("part.adoc"));
+        assert_eq!(doc.warnings[0].location.start.line, 3);
+    }
+}
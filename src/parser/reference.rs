@@ -0,0 +1,302 @@
+//! 章节 id 的生成和交叉引用的解析
+
+use std::borrow::Cow;
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use crate::ast::{Attribute, Block, BlockKind, Cell, CellStyle, Inline, InlineKind, Location, Position};
+use crate::attributes::Attributes;
+use crate::diagnostic::Diagnostic;
+use crate::parser::inline::parse_inlines;
+use crate::parser::{locate, Span};
+
+/// 由标题生成 id：转为小写，去掉标点，空白、`.` 和 `-` 替换为 `separator` 的第一个字符
+///
+/// 标题中的行内格式只保留文字，例如 `*Quick* start` 生成 `_quick_start`。
+pub fn generate_id(title: &str, prefix: &str, separator: &str) -> String {
+    let mut text = String::new();
+    plain_text(&parse_inlines(Span::new(title)), &mut text);
+
+    let separator = separator.chars().next();
+    let mut id = prefix.to_string();
+    let mut pending = false;
+    for c in text.to_lowercase().chars() {
+        if c.is_whitespace() || c == '.' || c == '-' {
+            pending = true;
+        } else if c.is_alphanumeric() || c == '_' {
+            // 没有前缀时开头不加分隔符，末尾的分隔符也不保留
+            if pending && !id.is_empty() {
+                id.extend(separator);
+            }
+            pending = false;
+            id.push(c);
+        }
+    }
+    id
+}
+
+fn plain_text(inlines: &[Inline], out: &mut String) {
+    for inline in inlines {
+        match &inline.kind {
+            InlineKind::Text(text) => out.push_str(text),
+            InlineKind::Formatted(text) => plain_text(text.children(), out),
+            InlineKind::Xref(xref) => out.push_str(xref.text.as_deref().or(xref.id).unwrap_or("")),
+        }
+    }
+}
+
+/// 为章节分配 id 并登记所有带 id 的块，然后检查文档内的交叉引用，为没有文字的引用填入目标的标题
+///
+/// 标题和单元格的文字没有保存行内节点，其中的引用只检查，转换时再按 id 查找目标的标题。
+///
+/// `attrs` 是按出现顺序排列的属性条目，每个章节使用它的标题之前的属性替换标题中的引用，
+/// 以及 `idprefix`（默认为 `_`）、`idseparator`（默认为 `_`）和 `sectids`（默认开启）。
+/// `overrides` 中的覆盖属性优先于这些默认值和属性条目。
+pub fn resolve_references<'a>(
+    source: &'a str,
+    blocks: &mut [Block<'a>],
+    attrs: &[&Attribute<'a>],
+    overrides: &[Attribute],
+) -> (HashMap<String, Option<String>>, Vec<Diagnostic>) {
+    let mut values = Attributes::with_overrides(&[]);
+    for (name, value) in [("sectids", ""), ("idprefix", "_"), ("idseparator", "_")] {
+        values.set(name, value);
+    }
+    values.apply_overrides(overrides);
+    let mut resolver = Resolver {
+        source,
+        attrs,
+        values,
+        references: HashMap::new(),
+        warnings: vec![],
+    };
+    resolver.register(blocks);
+    resolver.resolve(blocks);
+    (resolver.references, resolver.warnings)
+}
+
+struct Resolver<'r, 'a> {
+    /// 被解析的文档，用于计算标题和单元格中的引用的位置
+    source: &'a str,
+    /// 尚未应用的属性条目
+    attrs: &'r [&'r Attribute<'a>],
+    values: Attributes,
+    references: HashMap<String, Option<String>>,
+    warnings: Vec<Diagnostic>,
+}
+
+impl<'a> Resolver<'_, 'a> {
+    /// 应用 `position` 之前的属性条目，章节按文档顺序访问
+    fn advance(&mut self, position: Position) {
+        while let Some((attr, rest)) = self.attrs.split_first() {
+            if attr.location.start >= position {
+                break;
+            }
+            // 替换属性值时的警告由转换器给出
+            self.values.apply(attr);
+            self.attrs = rest;
+        }
+    }
+
+    fn register(&mut self, blocks: &mut [Block<'a>]) {
+        for block in blocks {
+            if let BlockKind::Section(section) = &mut block.kind {
+                self.advance(block.location.start);
+                let values = &self.values;
+                section.id = match block.metadata.id {
+                    Some(id) => Some(id.to_string()),
+                    None if values.get("sectids").is_some() => {
+                        let title = values.substitute(section.title).text;
+                        let separator = values.get("idseparator").unwrap_or("");
+                        let id = generate_id(&title, values.get("idprefix").unwrap_or(""), separator);
+                        let separator = separator.chars().next().map(String::from).unwrap_or_default();
+                        let mut unique = id.clone();
+                        for number in 2.. {
+                            if !self.references.contains_key(&unique) {
+                                break;
+                            }
+                            unique = format!("{id}{separator}{number}");
+                        }
+                        Some(unique)
+                    }
+                    None => None,
+                };
+                if let Some(id) = &section.id {
+                    let title = self.values.substitute(section.title).text.into_owned();
+                    self.insert(id.clone(), Some(title), block);
+                }
+            } else if let Some(id) = block.metadata.id {
+                let title = block.metadata.title.map(|title| self.values.substitute(title).text.into_owned());
+                self.insert(id.to_string(), title, block);
+            }
+
+            for children in children(&mut block.kind) {
+                self.register(children);
+            }
+        }
+    }
+
+    fn insert(&mut self, id: String, title: Option<String>, block: &Block) {
+        match self.references.entry(id) {
+            Entry::Occupied(entry) => {
+                let message = format!("id already in use: {}", entry.key());
+                self.warnings.push(Diagnostic::warning(message, block.location));
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(title);
+            }
+        }
+    }
+
+    fn resolve(&mut self, blocks: &mut [Block<'a>]) {
+        for block in blocks {
+            if let Some(title) = block.metadata.title {
+                self.resolve_text(title, block.location);
+            }
+            match &block.kind {
+                BlockKind::Section(section) => self.resolve_text(section.title, block.location),
+                BlockKind::Table(table) => {
+                    let header = table.header.iter().flatten().map(|cell| (cell, true));
+                    let body = table.rows.iter().chain(table.footer.iter()).flatten().map(|cell| (cell, false));
+                    for (cell, header) in header.chain(body) {
+                        self.resolve_cell(cell, header);
+                    }
+                }
+                _ => {}
+            }
+            for inlines in inlines(&mut block.kind) {
+                self.resolve_inlines(inlines);
+            }
+            for children in children(&mut block.kind) {
+                self.resolve(children);
+            }
+        }
+    }
+
+    /// 和转换器一样，表头的单元格整体作为行内文字，其他单元格按空行分段，`a` 和 `l` 样式的除外
+    fn resolve_cell(&mut self, cell: &Cell, header: bool) {
+        if header {
+            if cell.blocks.is_empty() {
+                self.resolve_text(&cell.text, cell.location);
+            }
+            return;
+        }
+        if matches!(cell.style, CellStyle::AsciiDoc | CellStyle::Literal) {
+            return;
+        }
+        for paragraph in cell.text.split("\n\n").map(str::trim).filter(|paragraph| !paragraph.is_empty()) {
+            self.resolve_text(paragraph, cell.location);
+        }
+    }
+
+    /// 检查没有保存行内节点的文字中的引用
+    ///
+    /// 警告的位置按 `text` 在文档中的位置换算，不是文档的切片时（例如 CSV 中带引号的值）为 `fallback`。
+    fn resolve_text(&mut self, text: &str, fallback: Location) {
+        let count = self.warnings.len();
+        self.resolve_inlines(&mut parse_inlines(Span::new(text)));
+        if self.warnings.len() == count {
+            return;
+        }
+        let range = self.source.as_bytes().as_ptr_range();
+        let base = range.contains(&text.as_ptr()).then(|| locate(self.source, text).start);
+        for warning in &mut self.warnings[count..] {
+            warning.location = match base {
+                Some(base) => Location {
+                    start: shift(warning.location.start, base),
+                    end: shift(warning.location.end, base),
+                },
+                None => fallback,
+            };
+        }
+    }
+
+    fn resolve_inlines(&mut self, inlines: &mut [Inline<'_>]) {
+        for inline in inlines {
+            match &mut inline.kind {
+                InlineKind::Formatted(text) => self.resolve_inlines(text.children_mut()),
+                // 其他文档中的 id 无法检查
+                InlineKind::Xref(xref) if xref.path.is_none() => {
+                    let Some(id) = xref.id else { continue };
+                    match self.references.get(id) {
+                        Some(title) => {
+                            if xref.text.is_none() {
+                                xref.text = title.clone().map(Cow::Owned);
+                            }
+                        }
+                        None => self
+                            .warnings
+                            .push(Diagnostic::warning(format!("possible invalid reference: {id}"), inline.location)),
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// 把相对于从 `base` 开始的文字的位置换算为文档中的位置
+fn shift(position: Position, base: Position) -> Position {
+    match position.line {
+        1 => Position {
+            line: base.line,
+            column: base.column + position.column - 1,
+        },
+        line => Position {
+            line: base.line + line - 1,
+            ..position
+        },
+    }
+}
+
+/// 块中直接包含的子块
+fn children<'b, 'a>(kind: &'b mut BlockKind<'a>) -> Vec<&'b mut [Block<'a>]> {
+    match kind {
+        BlockKind::Section(section) => vec![&mut section.blocks],
+        BlockKind::Example { blocks }
+        | BlockKind::Sidebar { blocks }
+        | BlockKind::Quote { blocks }
+        | BlockKind::Open { blocks }
+        | BlockKind::Admonition { blocks, .. } => vec![blocks],
+        BlockKind::UnorderedList { items } | BlockKind::OrderedList { items, .. } => {
+            items.iter_mut().map(|item| item.blocks.as_mut_slice()).collect()
+        }
+        BlockKind::DescriptionList { items } => items.iter_mut().map(|item| item.blocks.as_mut_slice()).collect(),
+        BlockKind::Table(table) => table
+            .header
+            .iter_mut()
+            .chain(table.rows.iter_mut())
+            .chain(table.footer.iter_mut())
+            .flatten()
+            .map(|cell| cell.blocks.as_mut_slice())
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// 块中直接包含的行内节点
+fn inlines<'b, 'a>(kind: &'b mut BlockKind<'a>) -> Vec<&'b mut [Inline<'a>]> {
+    match kind {
+        BlockKind::Paragraph { inlines, .. } => vec![inlines],
+        BlockKind::UnorderedList { items } | BlockKind::OrderedList { items, .. } => {
+            items.iter_mut().map(|item| item.inlines.as_mut_slice()).collect()
+        }
+        BlockKind::DescriptionList { items } => items.iter_mut().map(|item| item.inlines.as_mut_slice()).collect(),
+        BlockKind::CalloutList { items } => items.iter_mut().map(|item| item.inlines.as_mut_slice()).collect(),
+        _ => vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::reference::generate_id;
+
+    #[test]
+    pub fn test_generate_id() {
+        assert_eq!(generate_id("Getting Started", "_", "_"), "_getting_started");
+        assert_eq!(generate_id("*Quick* start: v1.2 - notes!", "_", "_"), "_quick_start_v1_2_notes");
+        assert_eq!(generate_id(" Leading and trailing. ", "", "-"), "leading-and-trailing");
+        assert_eq!(generate_id("No Separator", "sec-", ""), "sec-noseparator");
+        assert_eq!(generate_id("Ünïcode Title", "", "_"), "ünïcode_title");
+    }
+}
@@ -0,0 +1,199 @@
+use crate::ast::{Block, BlockKind, BlockMetadata, Location, Section, Title};
+use crate::diagnostic::Diagnostic;
+
+/// 扁平的文档主体，章节标题与块交替出现
+pub enum Node<'a> {
+    Title(Title<'a>, BlockMetadata<'a>),
+    Block(Block<'a>),
+}
+
+/// 按标题级别将扁平的节点序列组装成嵌套的章节树
+pub fn build_sections(nodes: Vec<Node<'_>>) -> (Vec<Block<'_>>, Vec<Diagnostic>) {
+    let mut blocks = vec![];
+    let mut warnings = vec![];
+    let mut open: Vec<(Section, BlockMetadata, Location)> = vec![];
+
+    for node in nodes {
+        match node {
+            Node::Title(title, metadata) => {
+                while open.last().is_some_and(|(section, ..)| section.level >= title.level) {
+                    close_section(&mut open, &mut blocks);
+                }
+
+                let expected = open.last().map_or(2, |(section, ..)| section.level + 1);
+                if title.level != expected {
+                    warnings.push(Diagnostic::warning(
+                        format!(
+                            "section title out of sequence: expected level {}, got level {}",
+                            expected - 1,
+                            title.level - 1,
+                        ),
+                        title.location,
+                    ));
+                }
+
+                open.push((
+                    Section {
+                        level: title.level,
+                        title: title.content.trim(),
+                        id: None,
+                        blocks: vec![],
+                    },
+                    metadata,
+                    title.location,
+                ));
+            }
+            Node::Block(block) => match open.last_mut() {
+                Some((section, _, location)) => {
+                    location.end = block.location.end;
+                    section.blocks.push(block);
+                }
+                None => blocks.push(block),
+            },
+        }
+    }
+
+    while !open.is_empty() {
+        close_section(&mut open, &mut blocks);
+    }
+
+    (blocks, warnings)
+}
+
+/// 关闭最内层的章节，父章节的范围随之延伸
+fn close_section<'a>(open: &mut Vec<(Section<'a>, BlockMetadata<'a>, Location)>, blocks: &mut Vec<Block<'a>>) {
+    if let Some((section, metadata, location)) = open.pop() {
+        let block = Block {
+            kind: BlockKind::Section(section),
+            metadata,
+            location,
+        };
+        match open.last_mut() {
+            Some((parent, _, parent_location)) => {
+                parent_location.end = location.end;
+                parent.blocks.push(block);
+            }
+            None => blocks.push(block),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{Block, BlockKind, BlockMetadata, Location, Position, Section, Title};
+    use crate::parser::section::{build_sections, Node};
+
+    fn line(line: usize) -> Location {
+        Location {
+            start: Position { line, column: 1 },
+            end: Position { line, column: 10 },
+        }
+    }
+
+    fn lines(start: usize, end: usize) -> Location {
+        Location {
+            start: line(start).start,
+            end: line(end).end,
+        }
+    }
+
+    fn paragraph(lines: &str, at: usize) -> Block<'_> {
+        Block {
+            kind: BlockKind::Paragraph { lines, inlines: vec![] },
+            metadata: BlockMetadata::default(),
+            location: line(at),
+        }
+    }
+
+    fn title(level: usize, content: &str, at: usize) -> Node<'_> {
+        Node::Title(Title { level, content, location: line(at) }, BlockMetadata::default())
+    }
+
+    #[test]
+    pub fn test_build_sections() {
+        let nodes = vec![
+            Node::Block(paragraph("Preamble.", 1)),
+            title(2, " One", 3),
+            Node::Block(paragraph("In one.", 5)),
+            title(3, " One.One", 7),
+            Node::Block(paragraph("In one.one.", 9)),
+            title(2, " Two", 11),
+        ];
+        let (blocks, warnings) = build_sections(nodes);
+
+        assert!(warnings.is_empty());
+        assert_eq!(
+            blocks,
+            vec![
+                paragraph("Preamble.", 1),
+                Block {
+                    kind: BlockKind::Section(Section {
+                        level: 2,
+                        title: "One",
+                        id: None,
+                        blocks: vec![
+                            paragraph("In one.", 5),
+                            Block {
+                                kind: BlockKind::Section(Section {
+                                    level: 3,
+                                    title: "One.One",
+                                    id: None,
+                                    blocks: vec![paragraph("In one.one.", 9)],
+                                }),
+                                metadata: BlockMetadata::default(),
+                                location: lines(7, 9),
+                            },
+                        ],
+                    }),
+                    metadata: BlockMetadata::default(),
+                    location: lines(3, 9),
+                },
+                Block {
+                    kind: BlockKind::Section(Section {
+                        level: 2,
+                        title: "Two",
+                        id: None,
+                        blocks: vec![],
+                    }),
+                    metadata: BlockMetadata::default(),
+                    location: line(11),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    pub fn test_section_out_of_sequence() {
+        let nodes = vec![title(2, " One", 1), title(4, " Deep", 3)];
+        let (blocks, warnings) = build_sections(nodes);
+
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].location.start.line, 3);
+        assert_eq!(
+            warnings[0].message,
+            "section title out of sequence: expected level 2, got level 3"
+        );
+        assert_eq!(
+            blocks,
+            vec![Block {
+                kind: BlockKind::Section(Section {
+                    level: 2,
+                    title: "One",
+                    id: None,
+                    blocks: vec![Block {
+                        kind: BlockKind::Section(Section {
+                            level: 4,
+                            title: "Deep",
+                            id: None,
+                            blocks: vec![],
+                        }),
+                        metadata: BlockMetadata::default(),
+                        location: line(3),
+                    }],
+                }),
+                metadata: BlockMetadata::default(),
+                location: lines(1, 3),
+            }]
+        );
+    }
+}
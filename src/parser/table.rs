@@ -0,0 +1,636 @@
+//! 表格：PSV（`|===`）、CSV（`,===`）和 DSV（`:===`）格式
+//!
+//! 分隔块解析完成后再按格式拆分单元格，`a` 样式单元格的内容由调用者解析。
+
+use std::borrow::Cow;
+use std::mem::take;
+
+use nom::Slice;
+
+use crate::ast::{Block, BlockMetadata, Cell, CellStyle, Column, HAlign, Table, VAlign};
+use crate::diagnostic::Diagnostic;
+use crate::parser::{location, trim, Span};
+
+/// 单元格说明，例如 `2+`、`.3+`、`3*`、`^.>m`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct CellSpec {
+    /// `3*` 表示把单元格重复三次
+    repeat: usize,
+    colspan: usize,
+    rowspan: usize,
+    halign: Option<HAlign>,
+    valign: Option<VAlign>,
+    style: Option<CellStyle>,
+}
+
+const PLAIN: CellSpec = CellSpec {
+    repeat: 1,
+    colspan: 1,
+    rowspan: 1,
+    halign: None,
+    valign: None,
+    style: None,
+};
+
+/// 列数以及所有单元格额外重复和跨越的行数之和的最小上限
+///
+/// 实际的上限是它和单元格数量中较大的一个，防止 `100000000*|x` 这样的说明耗尽内存。
+const MIN_LIMIT: usize = 1000;
+
+/// 拆分出来但还没有放入行中的单元格
+struct RawCell<'a> {
+    spec: CellSpec,
+    span: Span<'a>,
+    text: Cow<'a, str>,
+    /// 单元格所在的行，相对于表格内容的开头，用于推断列数和表头
+    line: usize,
+}
+
+fn halign(c: char) -> Option<HAlign> {
+    match c {
+        '<' => Some(HAlign::Left),
+        '^' => Some(HAlign::Center),
+        '>' => Some(HAlign::Right),
+        _ => None,
+    }
+}
+
+fn valign(c: char) -> Option<VAlign> {
+    match c {
+        '<' => Some(VAlign::Top),
+        '^' => Some(VAlign::Middle),
+        '>' => Some(VAlign::Bottom),
+        _ => None,
+    }
+}
+
+fn cell_style(c: char) -> Option<CellStyle> {
+    match c {
+        'd' => Some(CellStyle::Default),
+        'a' => Some(CellStyle::AsciiDoc),
+        'e' => Some(CellStyle::Emphasis),
+        'h' => Some(CellStyle::Header),
+        'l' => Some(CellStyle::Literal),
+        'm' => Some(CellStyle::Monospace),
+        's' => Some(CellStyle::Strong),
+        _ => None,
+    }
+}
+
+/// 去掉开头的数字，返回数字和剩余部分
+fn split_number(text: &str) -> (Option<usize>, &str) {
+    let len = text.len() - text.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+    (text[..len].parse().ok(), &text[len..])
+}
+
+/// 对齐方式、列宽和样式，没有给出的部分为 `None`
+#[derive(Default)]
+struct Format {
+    halign: Option<HAlign>,
+    valign: Option<VAlign>,
+    width: Option<usize>,
+    style: Option<CellStyle>,
+}
+
+/// 去掉开头的 `^`、`.>`、`m` 等格式，`width` 为 `true` 时对齐方式和样式之间可以有列宽
+fn split_format(mut rest: &str, width: bool) -> Option<(Format, &str)> {
+    let mut format = Format::default();
+    if let Some(align) = rest.chars().next().and_then(halign) {
+        format.halign = Some(align);
+        rest = &rest[1..];
+    }
+    if let Some(after) = rest.strip_prefix('.') {
+        format.valign = Some(after.chars().next().and_then(valign)?);
+        rest = &after[1..];
+    }
+    if width {
+        let (number, after) = split_number(rest);
+        format.width = number;
+        rest = after.strip_prefix('%').or_else(|| after.strip_prefix('~')).unwrap_or(after);
+    }
+    if let Some(style) = rest.chars().next().and_then(cell_style) {
+        format.style = Some(style);
+        rest = &rest[1..];
+    }
+    Some((format, rest))
+}
+
+/// 解析单元格说明，不是合法的说明时返回 `None`
+fn parse_cell_spec(spec: &str) -> Option<CellSpec> {
+    let mut result = PLAIN;
+    let (number, after) = split_number(spec);
+    let mut rest = spec;
+    if let (Some(repeat), Some(after)) = (number, after.strip_prefix('*')) {
+        result.repeat = repeat.max(1);
+        rest = after;
+    } else {
+        let (rows, after_rows) = match after.strip_prefix('.').map(split_number) {
+            Some((Some(rows), after_rows)) => (Some(rows), after_rows),
+            _ => (None, after),
+        };
+        if let Some(after_plus) = after_rows.strip_prefix('+').filter(|_| number.is_some() || rows.is_some()) {
+            result.colspan = number.unwrap_or(1).max(1);
+            result.rowspan = rows.unwrap_or(1).max(1);
+            rest = after_plus;
+        }
+    }
+
+    match split_format(rest, false)? {
+        (format, "") => Some(CellSpec {
+            halign: format.halign,
+            valign: format.valign,
+            style: format.style,
+            ..result
+        }),
+        _ => None,
+    }
+}
+
+/// 解析 `cols` 属性，例如 `3`、`1,2,3`、`3*` 或 `<1,^2m,>.>3`
+///
+/// 列数最多为 `limit`，返回的布尔值表示是否有列被舍弃。
+fn parse_columns(spec: &str, limit: usize) -> (Vec<Column>, bool) {
+    let spec = spec.trim();
+    if let Ok(count) = spec.parse::<usize>() {
+        return (vec![Column::default(); count.min(limit)], count > limit);
+    }
+
+    let mut columns = vec![];
+    for entry in spec.split([',', ';']) {
+        let entry = entry.trim();
+        let (repeat, entry) = match split_number(entry) {
+            (Some(repeat), rest) if rest.starts_with('*') => (repeat, &rest[1..]),
+            _ => (1, entry),
+        };
+        if columns.len() + repeat > limit {
+            columns.resize(limit, Column::default());
+            return (columns, true);
+        }
+        let defaults = Column::default();
+        let column = match split_format(entry, true) {
+            Some((format, "")) => Column {
+                width: format.width.unwrap_or(1).max(1),
+                halign: format.halign.unwrap_or(defaults.halign),
+                valign: format.valign.unwrap_or(defaults.valign),
+                style: format.style.unwrap_or(defaults.style),
+            },
+            _ => defaults,
+        };
+        columns.extend(std::iter::repeat_n(column, repeat));
+    }
+    (columns, false)
+}
+
+/// 没有被 `\` 转义的分隔符的位置
+fn separators(text: &str, separator: char) -> Vec<usize> {
+    text.match_indices(separator)
+        .map(|(index, _)| index)
+        .filter(|&index| !text[..index].ends_with('\\'))
+        .collect()
+}
+
+/// 在表格内容中只向后移动的位置
+///
+/// 从内容开头切片需要重新数一遍之前的换行符，逐个单元格这样做会使大表格的拆分变为平方时间；
+/// 每次只从上一个位置向后切片，行号的计算总共只扫描一遍内容。
+struct Cursor<'a> {
+    span: Span<'a>,
+    offset: usize,
+    first_line: u32,
+}
+
+impl<'a> Cursor<'a> {
+    fn new(content: Span<'a>) -> Self {
+        Cursor {
+            span: content,
+            offset: 0,
+            first_line: content.location_line(),
+        }
+    }
+
+    /// 从内容中 `offset` 处开始的部分，`offset` 不能小于上一次的值
+    fn at(&mut self, offset: usize) -> Span<'a> {
+        self.span = self.span.slice(offset - self.offset..);
+        self.offset = offset;
+        self.span
+    }
+
+    /// `offset` 所在的行，相对于内容的开头
+    fn line(&mut self, offset: usize) -> usize {
+        (self.at(offset).location_line() - self.first_line) as usize
+    }
+}
+
+/// `start..end` 之间的单元格，去掉首尾空白，`separator` 不为 `None` 时还原被转义的分隔符
+fn raw_cell<'a>(
+    cursor: &mut Cursor<'a>,
+    start: usize,
+    end: usize,
+    separator: Option<char>,
+    spec: CellSpec,
+) -> RawCell<'a> {
+    let line = cursor.line(start);
+    let span = trim(cursor.at(start).slice(..end - start));
+    let escaped = separator.map(|separator| (format!("\\{separator}"), separator.to_string()));
+    let text = match escaped {
+        Some((escaped, separator)) if span.fragment().contains(&escaped) => {
+            Cow::Owned(span.fragment().replace(&escaped, &separator))
+        }
+        _ => Cow::Borrowed(*span.fragment()),
+    };
+    RawCell {
+        spec,
+        span,
+        text,
+        line,
+    }
+}
+
+/// PSV：每个单元格以分隔符开始，分隔符之前可以有单元格说明
+///
+/// 说明必须位于行首或者跟在空白之后，例如 `2+|`、`a|`。
+fn psv_cells(content: Span<'_>, separator: char) -> Vec<RawCell<'_>> {
+    let text = *content.fragment();
+    let positions = separators(text, separator);
+    let Some(&first) = positions.first() else {
+        return vec![];
+    };
+
+    let mut spec = parse_cell_spec(text[..first].trim()).unwrap_or(PLAIN);
+    let mut cursor = Cursor::new(content);
+    let mut cells = vec![];
+    for (index, &position) in positions.iter().enumerate() {
+        let start = position + separator.len_utf8();
+        let end = positions.get(index + 1).copied().unwrap_or(text.len());
+        let segment = &text[start..end];
+
+        // 除了最后一个单元格，结尾的说明属于下一个单元格
+        let mut len = segment.len();
+        let mut next = PLAIN;
+        if index + 1 < positions.len() {
+            let whitespace = segment.char_indices().rev().find(|(_, c)| c.is_whitespace());
+            if let Some((at, c)) = whitespace {
+                let token_start = at + c.len_utf8();
+                if let Some(found) = parse_cell_spec(&segment[token_start..]) {
+                    len = token_start;
+                    next = found;
+                }
+            }
+        }
+        cells.push(raw_cell(&mut cursor, start, start + len, Some(separator), spec));
+        spec = next;
+    }
+    cells
+}
+
+/// CSV：每行一条记录，带引号的值可以包含分隔符和换行，`""` 表示一个引号
+fn csv_cells(content: Span<'_>, separator: char) -> Vec<RawCell<'_>> {
+    let text = *content.fragment();
+    let mut cursor = Cursor::new(content);
+    let mut cells = vec![];
+    let mut index = 0;
+    let mut record_start = true;
+    while index < text.len() {
+        let rest = &text[index..];
+        if record_start {
+            // 跳过空行
+            let line_len = rest.find('\n').map_or(rest.len(), |at| at + 1);
+            if rest[..line_len].trim().is_empty() {
+                index += line_len;
+                continue;
+            }
+        }
+        let line = cursor.line(index);
+
+        let quoted = rest.trim_start_matches(' ');
+        let (mut cell, after) = if let Some(inner) = quoted.strip_prefix('"') {
+            let start = text.len() - inner.len();
+            let mut end = start;
+            loop {
+                match text[end..].find('"') {
+                    Some(at) if text[end + at + 1..].starts_with('"') => end += at + 2,
+                    Some(at) => {
+                        end += at;
+                        break;
+                    }
+                    None => {
+                        end = text.len();
+                        break;
+                    }
+                }
+            }
+            let span = cursor.at(start).slice(..end - start);
+            let value = match span.fragment().contains("\"\"") {
+                true => Cow::Owned(span.fragment().replace("\"\"", "\"")),
+                false => Cow::Borrowed(*span.fragment()),
+            };
+            let cell = RawCell {
+                spec: PLAIN,
+                span,
+                text: value,
+                line,
+            };
+            // 跳过结束引号和之后直到分隔符的内容
+            let after = (end + 1).min(text.len());
+            let field_end = text[after..].find([separator, '\n']).map_or(text.len(), |at| after + at);
+            (cell, field_end)
+        } else {
+            let field_end = rest.find([separator, '\n']).map_or(text.len(), |at| index + at);
+            (raw_cell(&mut cursor, index, field_end, None, PLAIN), field_end)
+        };
+        cell.line = line;
+        cells.push(cell);
+
+        record_start = !text[after..].starts_with(separator);
+        index = after + text[after..].chars().next().map_or(0, char::len_utf8);
+    }
+    cells
+}
+
+/// DSV：每行一条记录，值之间用分隔符隔开，`\:` 表示分隔符本身
+fn dsv_cells(content: Span<'_>, separator: char) -> Vec<RawCell<'_>> {
+    let text = *content.fragment();
+    let mut cursor = Cursor::new(content);
+    let mut cells = vec![];
+    let mut offset = 0;
+    for line in text.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut field_start = start;
+        for position in separators(line, separator) {
+            cells.push(raw_cell(&mut cursor, field_start, start + position, Some(separator), PLAIN));
+            field_start = start + position + separator.len_utf8();
+        }
+        cells.push(raw_cell(&mut cursor, field_start, start + line.len(), Some(separator), PLAIN));
+    }
+    cells
+}
+
+/// 解析表格内容，`delimiter` 是分隔行的第一个字符
+///
+/// `format` 和 `separator` 属性可以覆盖由分隔行决定的格式和分隔符。没有 `cols`
+/// 属性时列数等于第一行的单元格数；第一行之后紧跟空行时它就是表头，除非有
+/// `noheader` 选项。
+///
+/// 列数、重复次数和跨行数超出上限时截断，并返回警告。
+pub fn parse_table<'a>(
+    content: Span<'a>,
+    delimiter: char,
+    metadata: &BlockMetadata,
+    attach: &mut impl FnMut(Span<'a>) -> Vec<Block<'a>>,
+) -> (Table<'a>, Vec<Diagnostic>) {
+    let format = metadata.attribute("format").unwrap_or(match delimiter {
+        ',' => "csv",
+        ':' => "dsv",
+        _ => "psv",
+    });
+    let default_separator = match format {
+        "csv" => ',',
+        "tsv" => '\t',
+        "dsv" => ':',
+        _ if delimiter == '!' => '!',
+        _ => '|',
+    };
+    let separator = metadata
+        .attribute("separator")
+        .and_then(|separator| separator.chars().next())
+        .unwrap_or(default_separator);
+    let mut cells = match format {
+        "csv" | "tsv" => csv_cells(content, separator),
+        "dsv" => dsv_cells(content, separator),
+        _ => psv_cells(content, separator),
+    };
+
+    let limit = cells.len().max(MIN_LIMIT);
+    let mut warnings = vec![];
+    let mut budget = limit;
+    for cell in &mut cells {
+        let spec = &mut cell.spec;
+        let capped = spec.colspan > limit || spec.repeat - 1 + spec.rowspan - 1 > budget;
+        spec.colspan = spec.colspan.min(limit);
+        spec.repeat = spec.repeat.min(budget + 1);
+        budget -= spec.repeat - 1;
+        spec.rowspan = spec.rowspan.min(budget + 1);
+        budget -= spec.rowspan - 1;
+        if capped {
+            let message = format!("table cell spans or repeats too many cells, limited to {limit}");
+            warnings.push(Diagnostic::warning(message, location(&cell.span)));
+        }
+    }
+
+    let mut columns = match metadata.attribute("cols").map(|spec| parse_columns(spec, limit)) {
+        Some((columns, capped)) => {
+            if capped {
+                let message = format!("table has too many columns, limited to {limit}");
+                warnings.push(Diagnostic::warning(message, location(&content)));
+            }
+            columns
+        }
+        None => vec![],
+    };
+    if columns.is_empty() {
+        let first_line = cells.first().map(|cell| cell.line);
+        let count = cells
+            .iter()
+            .take_while(|cell| Some(cell.line) == first_line)
+            .map(|cell| cell.spec.colspan * cell.spec.repeat)
+            .sum::<usize>();
+        columns = vec![Column::default(); count.clamp(1, limit)];
+    }
+
+    let mut lines = content.fragment().lines();
+    let implicit_header = lines.next().is_some_and(|line| !line.trim().is_empty())
+        && lines.next().is_some_and(|line| line.trim().is_empty());
+    let has_header = metadata.has_option("header") || (implicit_header && !metadata.has_option("noheader"));
+
+    // 按列数把单元格放入行中，跨行的单元格占据之后几行中相同的列
+    let mut rows: Vec<Vec<Cell>> = vec![];
+    let mut row = vec![];
+    let mut column = 0;
+    let mut occupied = vec![0usize; columns.len()];
+    for raw in cells {
+        for _ in 0..raw.spec.repeat {
+            loop {
+                while column < columns.len() && occupied[column] > 0 {
+                    column += 1;
+                }
+                if column < columns.len() {
+                    break;
+                }
+                rows.push(take(&mut row));
+                column = 0;
+                occupied.iter_mut().for_each(|count| *count = count.saturating_sub(1));
+            }
+
+            let colspan = raw.spec.colspan.min(columns.len() - column);
+            let defaults = columns[column];
+            // 表头不使用列的样式
+            let in_header = has_header && rows.is_empty();
+            let style = raw.spec.style.unwrap_or(if in_header { CellStyle::Default } else { defaults.style });
+            let blocks = if style == CellStyle::AsciiDoc { attach(raw.span) } else { vec![] };
+            row.push(Cell {
+                text: raw.text.clone(),
+                colspan,
+                rowspan: raw.spec.rowspan,
+                halign: raw.spec.halign.unwrap_or(defaults.halign),
+                valign: raw.spec.valign.unwrap_or(defaults.valign),
+                style,
+                blocks,
+                location: location(&raw.span),
+            });
+            occupied[column..column + colspan].fill(raw.spec.rowspan);
+            column += colspan;
+        }
+    }
+    if !row.is_empty() {
+        rows.push(row);
+    }
+
+    let header = if has_header && !rows.is_empty() { Some(rows.remove(0)) } else { None };
+    let footer = if metadata.has_option("footer") { rows.pop() } else { None };
+    let table = Table {
+        columns,
+        header,
+        rows,
+        footer,
+    };
+    (table, warnings)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ast::{BlockMetadata, CellStyle, Column, HAlign, Table, VAlign};
+    use crate::parser::table::{parse_cell_spec, parse_columns, parse_table, CellSpec, MIN_LIMIT, PLAIN};
+    use crate::parser::Span;
+
+    fn parse<'a>(content: &'a str, delimiter: char, metadata: &BlockMetadata) -> Table<'a> {
+        let (table, warnings) = parse_table(Span::new(content), delimiter, metadata, &mut |_| vec![]);
+        assert_eq!(warnings, vec![]);
+        table
+    }
+
+    /// 每行的单元格文字
+    fn texts(rows: &[Vec<crate::ast::Cell>]) -> Vec<Vec<String>> {
+        rows.iter()
+            .map(|row| row.iter().map(|cell| cell.text.to_string()).collect())
+            .collect()
+    }
+
+    #[test]
+    pub fn test_parse_cell_spec() {
+        assert_eq!(parse_cell_spec(""), Some(PLAIN));
+        assert_eq!(parse_cell_spec("2+").map(|spec| spec.colspan), Some(2));
+        assert_eq!(parse_cell_spec(".3+").map(|spec| (spec.colspan, spec.rowspan)), Some((1, 3)));
+        assert_eq!(parse_cell_spec("2.3+").map(|spec| (spec.colspan, spec.rowspan)), Some((2, 3)));
+        assert_eq!(parse_cell_spec("3*").map(|spec| spec.repeat), Some(3));
+        assert_eq!(
+            parse_cell_spec("^.>m"),
+            Some(CellSpec {
+                halign: Some(HAlign::Center),
+                valign: Some(VAlign::Bottom),
+                style: Some(CellStyle::Monospace),
+                ..PLAIN
+            })
+        );
+        assert_eq!(parse_cell_spec("a").and_then(|spec| spec.style), Some(CellStyle::AsciiDoc));
+        assert_eq!(parse_cell_spec("2"), None);
+        assert_eq!(parse_cell_spec("text"), None);
+    }
+
+    #[test]
+    pub fn test_parse_columns() {
+        assert_eq!(parse_columns("3", 10), (vec![Column::default(); 3], false));
+        assert_eq!(parse_columns("2*", 10).0.len(), 2);
+        assert_eq!(parse_columns("100000000", 10), (vec![Column::default(); 10], true));
+        assert_eq!(parse_columns("1,100000000*", 10).0.len(), 10);
+        let (columns, _) = parse_columns("<1,^2m, >.>3", 10);
+        assert_eq!(columns.iter().map(|column| column.width).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert_eq!(columns[1].halign, HAlign::Center);
+        assert_eq!(columns[1].style, CellStyle::Monospace);
+        assert_eq!((columns[2].halign, columns[2].valign), (HAlign::Right, VAlign::Bottom));
+    }
+
+    #[test]
+    pub fn test_parse_psv_table() {
+        let content = "|Name |Value\n\n|a |1\n|b |2 \\| 3\n\n2+|Total";
+        let table = parse(content, '|', &BlockMetadata::default());
+        assert_eq!(table.columns.len(), 2);
+        assert_eq!(texts(table.header.as_slice()), vec![vec!["Name", "Value"]]);
+        assert_eq!(
+            texts(&table.rows),
+            vec![vec!["a", "1"], vec!["b", "2 | 3"], vec!["Total"]]
+        );
+        assert_eq!(table.rows[2][0].colspan, 2);
+        assert_eq!(table.rows[0][0].location.start.line, 3);
+    }
+
+    #[test]
+    pub fn test_parse_table_spans() {
+        let metadata = BlockMetadata {
+            named: vec![("cols", "3*")],
+            options: vec!["footer"],
+            ..BlockMetadata::default()
+        };
+        let content = ".2+|A |B |C\n|D |E\n3*|x\n";
+        let table = parse(content, '|', &metadata);
+        assert_eq!(table.header, None);
+        assert_eq!(texts(&table.rows), vec![vec!["A", "B", "C"], vec!["D", "E"]]);
+        assert_eq!(table.rows[0][0].rowspan, 2);
+        assert_eq!(texts(table.footer.as_slice()), vec![vec!["x", "x", "x"]]);
+    }
+
+    #[test]
+    pub fn test_parse_csv_and_dsv_tables() {
+        let content = "Name,Quote\n\n\"Smith, J\",\"He said \"\"hi\"\"\"\nDoe, plain\n";
+        let table = parse(content, ',', &BlockMetadata::default());
+        assert_eq!(texts(table.header.as_slice()), vec![vec!["Name", "Quote"]]);
+        assert_eq!(
+            texts(&table.rows),
+            vec![vec!["Smith, J", "He said \"hi\""], vec!["Doe", "plain"]]
+        );
+        assert_eq!(table.rows[0][1].location.start.line, 3);
+        assert_eq!(table.rows[1][1].location.start.line, 4);
+
+        let metadata = BlockMetadata {
+            options: vec!["noheader"],
+            ..BlockMetadata::default()
+        };
+        let table = parse("a:b\\:c\n\nd:\n", ':', &metadata);
+        assert_eq!(table.header, None);
+        assert_eq!(texts(&table.rows), vec![vec!["a", "b:c"], vec!["d", ""]]);
+        assert_eq!(table.rows[1][0].location.start.line, 3);
+    }
+
+    #[test]
+    pub fn test_parse_table_limits() {
+        let parse = |content, metadata: &BlockMetadata| {
+            let (table, warnings) = parse_table(Span::new(content), '|', metadata, &mut |_| vec![]);
+            let messages: Vec<_> = warnings.into_iter().map(|warning| warning.message).collect();
+            (table, messages)
+        };
+
+        let (table, warnings) = parse("100000000*|x", &BlockMetadata::default());
+        assert_eq!(table.columns.len(), MIN_LIMIT);
+        assert_eq!(table.rows[0].len(), MIN_LIMIT);
+        assert_eq!(warnings, ["table cell spans or repeats too many cells, limited to 1000"]);
+
+        // 跨越所有列的单元格之后的空行数量也受限制
+        let (table, warnings) = parse("100000000+|x\n100000000.100000000+|y\n|z", &BlockMetadata::default());
+        assert_eq!(table.rows[0][0].colspan, MIN_LIMIT);
+        assert_eq!(table.rows[1][0].rowspan, MIN_LIMIT + 1);
+        assert_eq!(table.rows.len(), MIN_LIMIT + 3);
+        assert_eq!(warnings.len(), 2);
+
+        let metadata = BlockMetadata {
+            named: vec![("cols", "100000000*")],
+            ..BlockMetadata::default()
+        };
+        let (table, warnings) = parse("|a |b", &metadata);
+        assert_eq!(table.columns.len(), MIN_LIMIT);
+        assert_eq!(warnings, ["table has too many columns, limited to 1000"]);
+    }
+}
@@ -0,0 +1,158 @@
+//! 条件指令：`ifdef::`、`ifndef::`、`ifeval::` 和 `endif::`
+
+/// 条件指令的种类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConditionalKind {
+    Ifdef,
+    Ifndef,
+    Ifeval,
+    Endif,
+}
+
+impl ConditionalKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ConditionalKind::Ifdef => "ifdef",
+            ConditionalKind::Ifndef => "ifndef",
+            ConditionalKind::Ifeval => "ifeval",
+            ConditionalKind::Endif => "endif",
+        }
+    }
+}
+
+/// 一行条件指令，`text` 是方括号中的内容
+#[derive(Debug, PartialEq)]
+pub struct Conditional<'a> {
+    pub kind: ConditionalKind,
+    pub target: &'a str,
+    pub text: &'a str,
+}
+
+/// 识别条件指令行，`ifeval::` 必须有表达式且没有目标，`endif::` 的方括号必须为空
+pub fn parse_conditional(line: &str) -> Option<Conditional<'_>> {
+    let (name, rest) = line.trim_end().split_once("::")?;
+    let kind = match name {
+        "ifdef" => ConditionalKind::Ifdef,
+        "ifndef" => ConditionalKind::Ifndef,
+        "ifeval" => ConditionalKind::Ifeval,
+        "endif" => ConditionalKind::Endif,
+        _ => return None,
+    };
+    let (target, text) = rest.strip_suffix(']')?.split_once('[')?;
+    if target.contains(char::is_whitespace) {
+        return None;
+    }
+    let valid = match kind {
+        ConditionalKind::Ifdef | ConditionalKind::Ifndef => !target.is_empty(),
+        ConditionalKind::Ifeval => target.is_empty() && !text.trim().is_empty(),
+        ConditionalKind::Endif => text.is_empty(),
+    };
+    valid.then_some(Conditional { kind, target, text })
+}
+
+/// `ifdef::` 的目标是否成立：`a,b` 任一属性已定义，`a+b` 全部属性已定义
+pub fn defined(target: &str, is_set: impl Fn(&str) -> bool) -> bool {
+    if target.contains(',') {
+        target.split(',').any(is_set)
+    } else {
+        target.split('+').all(is_set)
+    }
+}
+
+/// `ifeval::` 表达式中的值，未加引号且能解析为数字的按数字比较
+#[derive(Debug, PartialEq, PartialOrd)]
+enum Value<'a> {
+    Number(f64),
+    String(&'a str),
+}
+
+fn value(text: &str) -> Value<'_> {
+    let text = text.trim();
+    for quote in ['"', '\''] {
+        if let Some(text) = text.strip_prefix(quote).and_then(|text| text.strip_suffix(quote)) {
+            return Value::String(text);
+        }
+    }
+    match text.parse() {
+        Ok(number) => Value::Number(number),
+        Err(_) => Value::String(text),
+    }
+}
+
+const OPERATORS: [&str; 6] = ["==", "!=", "<=", ">=", "<", ">"];
+
+/// 求值已替换过属性引用的 `ifeval::` 表达式，例如 `2 > 1` 和 `"a" == "a"`
+///
+/// 表达式不合法时返回 `None`，数字和字符串之间只能判断是否相等。
+pub fn evaluate(expr: &str) -> Option<bool> {
+    let (index, op) = expr
+        .char_indices()
+        .find_map(|(index, _)| OPERATORS.iter().find(|op| expr[index..].starts_with(**op)).map(|op| (index, *op)))?;
+    let lhs = &expr[..index];
+    let rhs = &expr[index + op.len()..];
+    if lhs.trim().is_empty() || rhs.trim().is_empty() {
+        return None;
+    }
+
+    let (lhs, rhs) = (value(lhs), value(rhs));
+    match op {
+        "==" => Some(lhs == rhs),
+        "!=" => Some(lhs != rhs),
+        _ if std::mem::discriminant(&lhs) != std::mem::discriminant(&rhs) => None,
+        "<=" => Some(lhs <= rhs),
+        ">=" => Some(lhs >= rhs),
+        "<" => Some(lhs < rhs),
+        _ => Some(lhs > rhs),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::preprocessor::conditional::{defined, evaluate, parse_conditional, Conditional, ConditionalKind};
+
+    #[test]
+    pub fn test_parse_conditional() {
+        assert_eq!(
+            parse_conditional("ifdef::a,b[]\n"),
+            Some(Conditional {
+                kind: ConditionalKind::Ifdef,
+                target: "a,b",
+                text: "",
+            })
+        );
+        assert_eq!(parse_conditional("ifndef::x[Only *without* x.]").unwrap().text, "Only *without* x.");
+        assert_eq!(parse_conditional("ifeval::[{level} > 2]").unwrap().kind, ConditionalKind::Ifeval);
+        assert_eq!(parse_conditional("endif::a[]").unwrap().target, "a");
+
+        assert!(parse_conditional("ifdef::[]").is_none());
+        assert!(parse_conditional("ifeval::x[1 == 1]").is_none());
+        assert!(parse_conditional("ifeval::[]").is_none());
+        assert!(parse_conditional("endif::[text]").is_none());
+        assert!(parse_conditional("ifdef::a b[]").is_none());
+        assert!(parse_conditional("iffy::a[]").is_none());
+    }
+
+    #[test]
+    pub fn test_defined() {
+        let is_set = |name: &str| name == "a" || name == "b";
+        assert!(defined("a", is_set));
+        assert!(defined("a,c", is_set));
+        assert!(!defined("c,d", is_set));
+        assert!(defined("a+b", is_set));
+        assert!(!defined("a+c", is_set));
+    }
+
+    #[test]
+    pub fn test_evaluate() {
+        assert_eq!(evaluate("3 > 2"), Some(true));
+        assert_eq!(evaluate("10 < 9"), Some(false));
+        assert_eq!(evaluate("2.0 == 2"), Some(true));
+        assert_eq!(evaluate("\"pro\" == \"pro\""), Some(true));
+        assert_eq!(evaluate("'a' != \"b\""), Some(true));
+        assert_eq!(evaluate("abc <= abd"), Some(true));
+        assert_eq!(evaluate("\"2\" == 2"), Some(false));
+        assert_eq!(evaluate("\"2\" < 3"), None);
+        assert_eq!(evaluate("1 =="), None);
+        assert_eq!(evaluate("no operator"), None);
+    }
+}
@@ -0,0 +1,351 @@
+//! `include::` 指令：读取被包含的文件，按 `lines=`、`tags=` 和 `indent=` 选取内容
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+use crate::ast::BlockMetadata;
+use crate::parser::metadata::parse_attrlist;
+use crate::parser::Span;
+
+/// 读取到的被包含文件
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Resolved {
+    /// 文件的规范名称，用于检测循环包含，也是其中相对路径的基准
+    pub path: String,
+    pub content: String,
+}
+
+/// 读取被包含文件的接口，可以由文件系统、内存中的映射或压缩包等实现
+pub trait IncludeResolver {
+    /// 读取 `target`，`parent` 是包含它的文件的规范名称，顶层文档没有路径时为 `None`
+    fn resolve(&self, target: &str, parent: Option<&str>) -> Result<Resolved, String>;
+
+    /// 顶层文档的规范名称，必须和包含它时 `resolve` 返回的 `path` 相同，否则无法检测循环包含
+    fn canonical(&self, path: &str) -> String {
+        path.to_string()
+    }
+}
+
+/// 从文件系统读取，相对路径相对于包含它的文件所在的目录
+#[derive(Debug, Clone, Default)]
+pub struct FileResolver {
+    /// 顶层文档所在的目录
+    pub base_dir: PathBuf,
+}
+
+impl IncludeResolver for FileResolver {
+    fn resolve(&self, target: &str, parent: Option<&str>) -> Result<Resolved, String> {
+        let dir = parent
+            .and_then(|parent| Path::new(parent).parent())
+            .unwrap_or(&self.base_dir);
+        let path = dir.join(target);
+        let content = fs::read_to_string(&path).map_err(|err| format!("{}: {err}", path.display()))?;
+        let path = fs::canonicalize(&path).unwrap_or(path);
+        Ok(Resolved {
+            path: path.display().to_string(),
+            content,
+        })
+    }
+
+    fn canonical(&self, path: &str) -> String {
+        let path = self.base_dir.join(path);
+        fs::canonicalize(&path).unwrap_or(path).display().to_string()
+    }
+}
+
+/// 内存中的文件，相对路径相对于包含它的文件所在的目录
+impl IncludeResolver for HashMap<String, String> {
+    fn resolve(&self, target: &str, parent: Option<&str>) -> Result<Resolved, String> {
+        let dir = parent.and_then(|parent| Path::new(parent).parent()).unwrap_or(Path::new(""));
+        let path = join(dir, target).map(|path| path.display().to_string());
+        let content = path
+            .as_ref()
+            .and_then(|path| self.get(path))
+            .ok_or_else(|| format!("{target}: no such file"))?;
+        Ok(Resolved {
+            path: path.unwrap_or_default(),
+            content: content.clone(),
+        })
+    }
+
+    fn canonical(&self, path: &str) -> String {
+        join(Path::new(""), path).map_or(path.to_string(), |path| path.display().to_string())
+    }
+}
+
+/// 按字面拼接相对目录 `dir` 和 `target`，结果超出 `dir` 的根或 `target` 是绝对路径时为 `None`
+///
+/// 不会访问文件系统，也就不会解析符号链接。
+pub fn join(dir: &Path, target: &str) -> Option<PathBuf> {
+    let mut path = dir.to_path_buf();
+    for component in Path::new(target).components() {
+        match component {
+            Component::Normal(name) => path.push(name),
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !path.pop() {
+                    return None;
+                }
+            }
+            Component::RootDir | Component::Prefix(_) => return None,
+        }
+    }
+    Some(path)
+}
+
+/// 按字面规范化绝对路径 `target`，位于绝对路径 `base` 之内时返回相对于 `base` 的路径
+///
+/// 和 [`join`] 一样不会访问文件系统。
+pub fn relative_to(base: &Path, target: &str) -> Option<PathBuf> {
+    if !base.is_absolute() {
+        return None;
+    }
+    let mut path = PathBuf::new();
+    for component in Path::new(target).components() {
+        match component {
+            Component::CurDir => {}
+            // 根目录的上一级仍然是根目录
+            Component::ParentDir => {
+                path.pop();
+            }
+            component => path.push(component),
+        }
+    }
+    path.strip_prefix(base).ok().map(Path::to_path_buf)
+}
+
+/// 一行 `include::target[attrs]` 指令
+#[derive(Debug, PartialEq)]
+pub struct Include<'a> {
+    pub target: &'a str,
+    pub attrs: &'a str,
+    pub metadata: BlockMetadata<'a>,
+}
+
+/// 识别 `include::target[attrs]` 指令行，目标中不能有空白
+pub fn parse_include(line: &str) -> Option<Include<'_>> {
+    let rest = line.trim_end().strip_prefix("include::")?;
+    let (target, attrs) = rest.strip_suffix(']')?.split_once('[')?;
+    if target.is_empty() || target.contains(char::is_whitespace) {
+        return None;
+    }
+    let metadata = match parse_attrlist(Span::new(attrs)) {
+        Ok((rest, metadata)) if rest.fragment().is_empty() => metadata,
+        _ => BlockMetadata::default(),
+    };
+    Some(Include { target, attrs, metadata })
+}
+
+/// 解析 `lines=1..3;5;7..-1`，范围之间用 `;` 或 `,` 分隔，`-1` 表示到文件末尾
+///
+/// 返回从 1 开始的闭区间，结束为 `None` 时一直到文件末尾。
+pub fn parse_line_ranges(spec: &str) -> Vec<(usize, Option<usize>)> {
+    spec.split([';', ','])
+        .map(str::trim)
+        .filter_map(|range| match range.split_once("..") {
+            Some((start, "-1" | "")) => Some((start.parse().ok()?, None)),
+            Some((start, end)) => Some((start.parse().ok()?, Some(end.parse().ok()?))),
+            None => range.parse().ok().map(|line| (line, Some(line))),
+        })
+        .collect()
+}
+
+/// `lines` 是从 `content` 中选取的行，返回它们在 `content` 中的行号
+pub fn line_numbers(content: &str, lines: &[&str]) -> Vec<usize> {
+    let newlines: Vec<usize> = content.match_indices('\n').map(|(index, _)| index).collect();
+    lines
+        .iter()
+        .map(|line| {
+            let offset = line.as_ptr() as usize - content.as_ptr() as usize;
+            newlines.partition_point(|&index| index < offset) + 1
+        })
+        .collect()
+}
+
+/// 按 `lines=` 选取行，行号从 1 开始
+pub fn select_lines<'a>(lines: &[&'a str], ranges: &[(usize, Option<usize>)]) -> Vec<&'a str> {
+    lines
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| {
+            let number = index + 1;
+            ranges
+                .iter()
+                .any(|&(start, end)| number >= start && end.is_none_or(|end| number <= end))
+        })
+        .map(|(_, line)| *line)
+        .collect()
+}
+
+/// 行中的 `tag::name[]` 或 `end::name[]`，返回是否为开始和标签名
+fn tag_directive(line: &str) -> Option<(bool, &str)> {
+    for (prefix, start) in [("tag::", true), ("end::", false)] {
+        let Some(index) = line.find(prefix) else { continue };
+        let rest = &line[index + prefix.len()..];
+        let Some(end) = rest.find("[]") else { continue };
+        let name = &rest[..end];
+        if !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') {
+            return Some((start, name));
+        }
+    }
+    None
+}
+
+/// 按 `tags=a;!b;*;**` 选取行，标签所在的行本身总是被删除
+///
+/// 返回选中的行和在文件中没有找到的标签。
+pub fn select_tags<'a, 's>(lines: &[&'a str], spec: &'s str) -> (Vec<&'a str>, Vec<&'s str>) {
+    let mut rules = vec![];
+    let mut wildcard = None;
+    let mut globstar = false;
+    for tag in spec.split([';', ',']).map(str::trim).filter(|tag| !tag.is_empty()) {
+        let (include, name) = match tag.strip_prefix('!') {
+            Some(name) => (false, name),
+            None => (true, tag),
+        };
+        match name {
+            "**" => globstar = include,
+            "*" => wildcard = Some(include),
+            _ => rules.push((name, include)),
+        }
+    }
+    // 只有排除的标签时，保留不在标签中的行
+    let base = globstar || (wildcard.is_none() && rules.iter().all(|(_, include)| !include));
+
+    let mut selected = vec![];
+    let mut found = vec![];
+    let mut open: Vec<&str> = vec![];
+    for &line in lines {
+        match tag_directive(line) {
+            Some((true, name)) => {
+                found.push(name);
+                open.push(name);
+            }
+            Some((false, name)) => {
+                if let Some(index) = open.iter().rposition(|open| *open == name) {
+                    open.truncate(index);
+                }
+            }
+            None => {
+                let rule = open
+                    .iter()
+                    .rev()
+                    .find_map(|name| rules.iter().find(|(rule, _)| rule == name).map(|(_, include)| *include));
+                let include = match rule {
+                    Some(include) => include,
+                    None if !open.is_empty() => wildcard.unwrap_or(base),
+                    None => base,
+                };
+                if include {
+                    selected.push(line);
+                }
+            }
+        }
+    }
+
+    let missing = rules
+        .iter()
+        .filter(|(name, include)| *include && !found.contains(name))
+        .map(|(name, _)| *name)
+        .collect();
+    (selected, missing)
+}
+
+/// 去掉各行共同的缩进后再缩进 `indent` 个空格，空行保持为空
+pub fn reindent(lines: &[&str], indent: usize) -> Vec<String> {
+    let common = lines
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start_matches([' ', '\t']).len())
+        .min()
+        .unwrap_or(0);
+    let padding = " ".repeat(indent);
+    lines
+        .iter()
+        .map(|line| {
+            if line.trim().is_empty() {
+                String::new()
+            } else {
+                format!("{padding}{}", &line[common..])
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use crate::preprocessor::include::{
+        join, line_numbers, parse_include, parse_line_ranges, reindent, relative_to, select_lines, select_tags,
+    };
+
+    #[test]
+    pub fn test_parse_include() {
+        let include = parse_include("include::chapters/intro.adoc[lines=\"1..3;5\",leveloffset=+1]\n").unwrap();
+        assert_eq!(include.target, "chapters/intro.adoc");
+        assert_eq!(include.metadata.attribute("lines"), Some("1..3;5"));
+        assert_eq!(include.metadata.attribute("leveloffset"), Some("+1"));
+
+        assert!(parse_include("include::a.adoc").is_none());
+        assert!(parse_include("include::[]").is_none());
+        assert!(parse_include("include::a b.adoc[]").is_none());
+        assert!(parse_include(" include::a.adoc[]").is_none());
+    }
+
+    #[test]
+    pub fn test_join() {
+        assert_eq!(join(Path::new("a/b"), "../c/./d.adoc"), Some(PathBuf::from("a/c/d.adoc")));
+        assert_eq!(join(Path::new(""), "d.adoc"), Some(PathBuf::from("d.adoc")));
+        assert_eq!(join(Path::new("a"), "../../d.adoc"), None);
+        assert_eq!(join(Path::new("a"), "/etc/passwd"), None);
+
+        let base = Path::new("/srv/docs");
+        assert_eq!(relative_to(base, "/srv/docs/./a/../b.adoc"), Some(PathBuf::from("b.adoc")));
+        assert_eq!(relative_to(base, "/srv/docs/../secret.txt"), None);
+        assert_eq!(relative_to(base, "/srv/docs2/a.adoc"), None);
+        assert_eq!(relative_to(base, "a.adoc"), None);
+        assert_eq!(relative_to(Path::new(""), "/srv/docs/a.adoc"), None);
+    }
+
+    #[test]
+    pub fn test_select_lines() {
+        assert_eq!(parse_line_ranges("1..2;4, 6..-1"), vec![(1, Some(2)), (4, Some(4)), (6, None)]);
+
+        let lines = ["a", "b", "c", "d", "e", "f", "g"];
+        assert_eq!(select_lines(&lines, &parse_line_ranges("1..2;4;6..-1")), ["a", "b", "d", "f", "g"]);
+        assert_eq!(select_lines(&lines, &parse_line_ranges("x")), Vec::<&str>::new());
+
+        let content = "a\n\nc\nd";
+        let lines: Vec<&str> = content.lines().collect();
+        let selected = select_lines(&lines, &parse_line_ranges("2..4"));
+        assert_eq!(line_numbers(content, &selected), [2, 3, 4]);
+    }
+
+    #[test]
+    pub fn test_select_tags() {
+        let lines = [
+            "before",
+            "// tag::a[]",
+            "in a",
+            "# tag::b[]",
+            "in b",
+            "# end::b[]",
+            "// end::a[]",
+            "after",
+        ];
+        assert_eq!(select_tags(&lines, "a"), (vec!["in a", "in b"], vec![]));
+        assert_eq!(select_tags(&lines, "b;missing"), (vec!["in b"], vec!["missing"]));
+        assert_eq!(select_tags(&lines, "a;!b"), (vec!["in a"], vec![]));
+        assert_eq!(select_tags(&lines, "!b"), (vec!["before", "in a", "after"], vec![]));
+        assert_eq!(select_tags(&lines, "*"), (vec!["in a", "in b"], vec![]));
+        assert_eq!(select_tags(&lines, "**"), (vec!["before", "in a", "in b", "after"], vec![]));
+    }
+
+    #[test]
+    pub fn test_reindent() {
+        assert_eq!(reindent(&["    fn a() {", "", "        b();", "    }"], 2), ["  fn a() {", "", "      b();", "  }"]);
+        assert_eq!(reindent(&["\tx", "\t\ty"], 0), ["x", "\ty"]);
+    }
+}
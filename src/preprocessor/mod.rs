@@ -0,0 +1,735 @@
+//! 解析之前按行处理源文本的预处理器，负责展开 `include::` 指令和条件指令
+
+use std::path::{Path, PathBuf};
+
+use crate::ast::{Attribute, Location, Position};
+use crate::attributes::Attributes;
+use crate::diagnostic::Diagnostic;
+use crate::parser::header::parse_doc_attr;
+use crate::parser::Span;
+use crate::safe_mode::SafeMode;
+
+pub mod conditional;
+pub mod include;
+
+pub use include::{FileResolver, IncludeResolver, Resolved};
+
+use conditional::{defined, evaluate, parse_conditional, Conditional, ConditionalKind};
+use include::{
+    join, line_numbers, parse_include, parse_line_ranges, reindent, relative_to, select_lines, select_tags, Include,
+};
+
+/// 默认的最大包含深度
+pub const MAX_INCLUDE_DEPTH: usize = 64;
+
+/// 预处理选项
+pub struct Options<'a> {
+    /// 读取被包含的文件，为 `None` 时 `include::` 指令保持原样
+    pub resolver: Option<&'a dyn IncludeResolver>,
+    /// 顶层文档的路径，是其中相对路径的基准
+    pub path: Option<&'a str>,
+    /// `include::` 的最大嵌套深度，覆盖属性和文档中的 `max-include-depth` 优先
+    pub max_include_depth: usize,
+    /// 覆盖文档属性，条件指令按它和文档中已出现的属性条目求值
+    pub attributes: Vec<Attribute<'a>>,
+    /// `safe` 和 `server` 只允许包含顶层文档所在目录之内的文件，`secure` 禁用 `include::`
+    pub safe_mode: SafeMode,
+}
+
+impl Default for Options<'_> {
+    fn default() -> Self {
+        Options {
+            resolver: None,
+            path: None,
+            max_include_depth: MAX_INCLUDE_DEPTH,
+            attributes: vec![],
+            safe_mode: SafeMode::default(),
+        }
+    }
+}
+
+/// 预处理结果，解析器处理的是其中的 `source`
+#[derive(Debug, Default)]
+pub struct Output {
+    pub source: String,
+    /// 位置指向包含指令所在文件中的行，`file` 是其中的被包含文件
+    pub warnings: Vec<Diagnostic>,
+    /// 被展开的文件，用于显示其中的诊断
+    pub files: Vec<Resolved>,
+    /// 展开后每一行的来源
+    pub lines: Vec<Origin>,
+}
+
+/// 展开后的一行在源文件中的位置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Origin {
+    /// 所在的被包含文件在 [`Output::files`] 中的下标，为 `None` 时位于顶层文档
+    pub file: Option<usize>,
+    /// 行号，从 1 开始
+    pub line: usize,
+}
+
+impl Output {
+    /// 把指向展开后文本的诊断改为指向源文件中的行
+    ///
+    /// 列号不变，跨越不同文件的范围只标记到起始行的末尾。
+    pub fn remap(&self, diagnostic: &Diagnostic) -> Diagnostic {
+        let mut diagnostic = diagnostic.clone();
+        let origin = |line: usize| {
+            let last = self.lines.len().min(line.max(1)).checked_sub(1)?;
+            let origin = self.lines[last];
+            Some(Origin {
+                line: origin.line + line.saturating_sub(last + 1),
+                ..origin
+            })
+        };
+        let (Some(start), Some(end)) = (origin(diagnostic.location.start.line), origin(diagnostic.location.end.line)) else {
+            return diagnostic;
+        };
+        diagnostic.location.start.line = start.line;
+        diagnostic.location.end = if end.file == start.file {
+            Position { line: end.line, ..diagnostic.location.end }
+        } else {
+            Position { line: start.line + 1, column: 1 }
+        };
+        diagnostic.file = start.file.map(|file| self.files[file].path.clone());
+        diagnostic
+    }
+}
+
+/// 展开源文本中的 `include::` 指令，删除条件不成立的行
+pub fn preprocess(source: &str, options: &Options) -> Output {
+    let mut attrs = Attributes::with_overrides(&options.attributes);
+    attrs.apply_overrides(&options.safe_mode.attributes());
+    let root = options
+        .path
+        .map(|path| options.resolver.map_or(path.to_string(), |resolver| resolver.canonical(path)));
+    // 没有路径的顶层文档以读取器的当前目录为基准
+    let base = match (&root, options.resolver) {
+        (Some(root), _) => Some(Path::new(root).parent().map(Path::to_path_buf).unwrap_or_default()),
+        (None, Some(resolver)) => Some(PathBuf::from(resolver.canonical("."))),
+        (None, None) => None,
+    };
+    let mut preprocessor = Preprocessor {
+        options,
+        root: root.clone(),
+        base,
+        stack: root.iter().cloned().collect(),
+        dirs: vec![PathBuf::new()],
+        attrs,
+        conditionals: vec![],
+        out: String::with_capacity(source.len()),
+        warnings: vec![],
+        files: vec![],
+        lines: vec![],
+    };
+    preprocessor.lines(source, root.as_deref(), 0, None);
+    for open in std::mem::take(&mut preprocessor.conditionals) {
+        let message = format!("unterminated preprocessor conditional directive: {}", open.directive);
+        preprocessor.warn(message, open.location, open.file.as_deref(), None);
+    }
+    Output {
+        source: preprocessor.out,
+        warnings: preprocessor.warnings,
+        files: preprocessor.files,
+        lines: preprocessor.lines,
+    }
+}
+
+struct Preprocessor<'o> {
+    options: &'o Options<'o>,
+    /// 顶层文档的规范名称
+    root: Option<String>,
+    /// 顶层文档所在目录的规范名称，`safe` 及以上的模式只允许包含其中的文件
+    base: Option<PathBuf>,
+    /// 正在展开的文件，用于检测循环包含
+    stack: Vec<String>,
+    /// 正在展开的文件相对于顶层文档的目录
+    dirs: Vec<PathBuf>,
+    /// 到当前行为止出现的属性条目
+    attrs: Attributes,
+    /// 尚未遇到 `endif::` 的条件指令
+    conditionals: Vec<Open>,
+    out: String,
+    warnings: Vec<Diagnostic>,
+    files: Vec<Resolved>,
+    /// `out` 中每一行的来源
+    lines: Vec<Origin>,
+}
+
+/// 一个尚未结束的条件指令
+struct Open {
+    directive: String,
+    target: String,
+    /// 条件不成立或外层条件不成立时跳过其中的行
+    skip: bool,
+    location: Location,
+    file: Option<String>,
+}
+
+/// 可以用 `\` 转义的指令
+const DIRECTIVES: [&str; 5] = ["include::", "ifdef::", "ifndef::", "ifeval::", "endif::"];
+
+impl Preprocessor<'_> {
+    /// 处理一个文件的全部行，`path` 为 `None` 表示没有路径的顶层文档
+    ///
+    /// `numbers` 是各行在文件中的行号，只选取了部分行时用于让诊断指向原来的行。
+    fn lines(&mut self, source: &str, path: Option<&str>, leveloffset: isize, numbers: Option<&[usize]>) {
+        let mut verbatim = None;
+        for (index, line) in source.split_inclusive('\n').enumerate() {
+            let number = numbers.and_then(|numbers| numbers.get(index).copied()).unwrap_or(index + 1);
+            let location = Location::covering(Position { line: number, column: 1 }, line.trim_end());
+            if let Some(conditional) = parse_conditional(line) {
+                self.conditional(&conditional, line, location, path);
+                continue;
+            }
+            if self.skipping() {
+                continue;
+            }
+            if let Some(rest) = line.strip_prefix('\\').filter(|rest| DIRECTIVES.iter().any(|d| rest.starts_with(d))) {
+                self.emit(rest, path, number);
+                continue;
+            }
+            if let Some(include) = parse_include(line) {
+                self.include(&include, line, location, path, leveloffset);
+                continue;
+            }
+
+            let content = line.trim_end();
+            match verbatim {
+                Some(delimiter) if delimiter == content => verbatim = None,
+                Some(_) => {}
+                None if is_verbatim_delimiter(content) => verbatim = Some(content),
+                None if content.starts_with(':') => {
+                    if let Ok((rest, attr)) = parse_doc_attr(Span::new(content)) {
+                        // 替换属性值时的警告由转换器给出
+                        if rest.fragment().is_empty() {
+                            self.attrs.apply(&attr);
+                        }
+                    }
+                }
+                None if leveloffset != 0 => {
+                    if let Some(heading) = offset_heading(line, leveloffset) {
+                        self.emit(&heading, path, number);
+                        continue;
+                    }
+                }
+                None => {}
+            }
+            self.emit(line, path, number);
+        }
+    }
+
+    fn skipping(&self) -> bool {
+        self.conditionals.last().is_some_and(|open| open.skip)
+    }
+
+    fn conditional(&mut self, conditional: &Conditional, line: &str, location: Location, file: Option<&str>) {
+        let directive = line.trim_end();
+        if conditional.kind == ConditionalKind::Endif {
+            match self.conditionals.last() {
+                None => {
+                    self.warn(format!("unmatched preprocessor directive: {directive}"), location, file, None);
+                }
+                Some(open) if !conditional.target.is_empty() && conditional.target != open.target => {
+                    let message = format!("mismatched preprocessor directive: {directive}, expected endif::{}[]", open.target);
+                    self.warn(message, location, file, None);
+                }
+                Some(_) => {
+                    self.conditionals.pop();
+                }
+            }
+            return;
+        }
+
+        let skipping = self.skipping();
+        let satisfied = !skipping
+            && match conditional.kind {
+                ConditionalKind::Ifdef => defined(conditional.target, |name| self.attrs.get(name).is_some()),
+                ConditionalKind::Ifndef => !defined(conditional.target, |name| self.attrs.get(name).is_some()),
+                _ => {
+                    let expr = self.attrs.substitute(conditional.text).text;
+                    evaluate(&expr).unwrap_or_else(|| {
+                        let message = format!("malformed preprocessor directive: {directive}");
+                        self.warn(message, location, file, None);
+                        false
+                    })
+                }
+            };
+
+        // `ifdef::name[text]` 只作用于方括号中的这一行
+        if conditional.kind != ConditionalKind::Ifeval && !conditional.text.is_empty() {
+            if satisfied {
+                self.emit(&format!("{}\n", conditional.text), file, location.start.line);
+            }
+            return;
+        }
+        self.conditionals.push(Open {
+            directive: directive.to_string(),
+            target: conditional.target.to_string(),
+            skip: !satisfied,
+            location,
+            file: file.map(str::to_string),
+        });
+    }
+
+    fn include(&mut self, include: &Include, line: &str, location: Location, parent: Option<&str>, leveloffset: isize) {
+        if self.options.safe_mode >= SafeMode::Secure {
+            self.warn("include directive is disabled in secure mode".to_string(), location, parent, None);
+            return self.unresolved(include, location, parent);
+        }
+        let Some(resolver) = self.options.resolver else {
+            self.emit(line, parent, location.start.line);
+            return;
+        };
+
+        let depth = self.stack.len() - usize::from(self.options.path.is_some());
+        let max_depth = self.max_include_depth();
+        if depth >= max_depth {
+            let message = format!("maximum include depth of {max_depth} exceeded");
+            self.warn(message, location, parent, None);
+            return self.unresolved(include, location, parent);
+        }
+
+        let target = self.attrs.substitute(include.target).text.into_owned();
+        let dir = self.dirs.last().map(PathBuf::as_path).unwrap_or(Path::new(""));
+        // 绝对路径在顶层文档所在的目录之内时也允许包含，例如 `{docdir}/chapter.adoc`
+        let base = self.root.as_deref().and_then(|root| Path::new(root).parent());
+        let relative = join(dir, &target).or_else(|| base.and_then(|base| relative_to(base, &target)));
+        let outside = format!("include file is outside the base directory: {target}");
+        if relative.is_none() && self.options.safe_mode >= SafeMode::Safe {
+            self.warn(outside, location, parent, None);
+            return self.unresolved(include, location, parent);
+        }
+        let resolved = match resolver.resolve(&target, parent) {
+            Ok(resolved) => resolved,
+            Err(err) => {
+                self.warn(format!("include file not found: {}", include.target), location, parent, Some(err));
+                return self.unresolved(include, location, parent);
+            }
+        };
+        // 按字面检查时无法发现符号链接，读取到的文件的规范名称也必须位于基准目录之内
+        let canonical = self
+            .base
+            .as_deref()
+            .and_then(|base| Path::new(&resolved.path).strip_prefix(base).ok())
+            .map(Path::to_path_buf);
+        if canonical.is_none() && self.options.safe_mode >= SafeMode::Safe {
+            self.warn(outside, location, parent, None);
+            return self.unresolved(include, location, parent);
+        }
+        if let Some(index) = self.stack.iter().position(|path| *path == resolved.path) {
+            let mut chain = self.stack[index..].to_vec();
+            chain.push(resolved.path);
+            self.warn(format!("include cycle detected: {}", chain.join(" -> ")), location, parent, None);
+            return self.unresolved(include, location, parent);
+        }
+
+        let content = resolved.content;
+        let lines: Vec<&str> = content.lines().collect();
+        let metadata = &include.metadata;
+        let lines = if let Some(spec) = metadata.attribute("lines") {
+            select_lines(&lines, &parse_line_ranges(spec))
+        } else if let Some(spec) = metadata.attribute("tags").or(metadata.attribute("tag")) {
+            let (selected, missing) = select_tags(&lines, spec);
+            for tag in missing {
+                let message = format!("tag '{tag}' not found in include file: {}", include.target);
+                self.warn(message, location, parent, None);
+            }
+            selected
+        } else {
+            lines
+        };
+        let numbers = line_numbers(&content, &lines);
+        let lines = match metadata.attribute("indent").and_then(|indent| indent.parse().ok()) {
+            Some(indent) => reindent(&lines, indent),
+            None => lines.into_iter().map(String::from).collect(),
+        };
+        let selected: String = lines.iter().map(|line| format!("{line}\n")).collect();
+        let leveloffset = match metadata.attribute("leveloffset") {
+            Some(offset) if offset.starts_with(['+', '-']) => leveloffset + offset.parse::<isize>().unwrap_or(0),
+            Some(offset) => offset.parse().unwrap_or(leveloffset),
+            None => leveloffset,
+        };
+
+        // 其中的相对路径由读取器相对于规范名称解析，按字面检查时也以它为准
+        let dir = canonical.or(relative).and_then(|relative| relative.parent().map(Path::to_path_buf));
+        self.dirs.push(dir.unwrap_or_default());
+        if !self.files.iter().any(|file| file.path == resolved.path) {
+            self.files.push(Resolved {
+                path: resolved.path.clone(),
+                content: content.clone(),
+            });
+        }
+        self.stack.push(resolved.path);
+        let path = self.stack.last().cloned();
+        self.lines(&selected, path.as_deref(), leveloffset, Some(&numbers));
+        self.stack.pop();
+        self.dirs.pop();
+    }
+
+    /// 按到当前行为止的 `max-include-depth` 属性，无效时使用选项中的值
+    fn max_include_depth(&self) -> usize {
+        self.attrs
+            .get("max-include-depth")
+            .and_then(|depth| depth.trim().parse().ok())
+            .unwrap_or(self.options.max_include_depth)
+    }
+
+    /// 无法展开的指令替换为一行说明，和 Asciidoctor 一致
+    fn unresolved(&mut self, include: &Include, location: Location, parent: Option<&str>) {
+        let text = format!(
+            "Unresolved directive in {} - include::{}[{}]\n",
+            parent.unwrap_or("<stdin>"),
+            include.target,
+            include.attrs
+        );
+        self.emit(&text, parent, location.start.line);
+    }
+
+    /// 输出一行，`line` 是它在文件 `file` 中的行号
+    fn emit(&mut self, text: &str, file: Option<&str>, line: usize) {
+        let file = file
+            .filter(|file| Some(*file) != self.root.as_deref())
+            .and_then(|file| self.files.iter().position(|resolved| resolved.path == file));
+        self.out.push_str(text);
+        self.lines.push(Origin { file, line });
+    }
+
+    /// `file` 是指令所在的文件，不是顶层文档时记录在诊断中
+    fn warn(&mut self, message: String, location: Location, file: Option<&str>, note: Option<String>) {
+        let mut warning = Diagnostic::warning(message, location);
+        warning.file = file.filter(|file| Some(*file) != self.root.as_deref()).map(str::to_string);
+        warning.notes.extend(note);
+        self.warnings.push(warning);
+    }
+}
+
+/// 原样块的分隔行，其中的 `=` 开头的行不是章节标题
+fn is_verbatim_delimiter(line: &str) -> bool {
+    line.len() >= 4 && ['-', '.', '+', '/'].iter().any(|&c| line.chars().all(|ch| ch == c))
+}
+
+/// 按 `leveloffset` 调整章节标题的级别，级别最小为文档标题
+fn offset_heading(line: &str, leveloffset: isize) -> Option<String> {
+    let level = line.len() - line.trim_start_matches('=').len();
+    if level == 0 || !line[level..].starts_with([' ', '\t']) {
+        return None;
+    }
+    let offset = (level as isize + leveloffset).max(1) as usize;
+    Some(format!("{}{}", "=".repeat(offset), &line[level..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::fs;
+
+    use crate::ast::Attribute;
+    use crate::parser::parse_document;
+    use crate::preprocessor::{preprocess, FileResolver, IncludeResolver, Options};
+    use crate::safe_mode::SafeMode;
+
+    fn files(files: &[(&str, &str)]) -> HashMap<String, String> {
+        files.iter().map(|(path, content)| (path.to_string(), content.to_string())).collect()
+    }
+
+    fn expand(source: &str, resolver: &dyn IncludeResolver) -> (String, Vec<String>) {
+        let options = Options {
+            resolver: Some(resolver),
+            path: Some("main.adoc"),
+            ..Options::default()
+        };
+        let output = preprocess(source, &options);
+        let warnings = output.warnings.into_iter().map(|warning| warning.message).collect();
+        (output.source, warnings)
+    }
+
+    #[test]
+    pub fn test_preprocess_include() {
+        let resolver = files(&[
+            ("intro.adoc", "= Intro\n\nText.\n\ninclude::detail.adoc[leveloffset=+1]"),
+            ("detail.adoc", "= Detail\n\n----\n= not a title\n----\n"),
+            ("code.rs", "// tag::main[]\n    fn main() {\n    }\n// end::main[]\nfn other() {}\n"),
+        ]);
+
+        let (source, warnings) = expand("= Doc\n\ninclude::intro.adoc[leveloffset=+1]\n\nEnd.\n", &resolver);
+        assert_eq!(
+            source,
+            "= Doc\n\n== Intro\n\nText.\n\n=== Detail\n\n----\n= not a title\n----\n\nEnd.\n"
+        );
+        assert!(warnings.is_empty());
+
+        let (source, _) = expand("----\ninclude::code.rs[tag=main,indent=2]\n----\n", &resolver);
+        assert_eq!(source, "----\n  fn main() {\n  }\n----\n");
+
+        let (source, _) = expand("include::code.rs[lines=5]\n\\include::code.rs[]\n", &resolver);
+        assert_eq!(source, "fn other() {}\ninclude::code.rs[]\n");
+
+        let options = Options::default();
+        assert_eq!(preprocess("include::code.rs[]\n", &options).source, "include::code.rs[]\n");
+    }
+
+    #[test]
+    pub fn test_preprocess_include_errors() {
+        let resolver = files(&[
+            ("a.adoc", "A\ninclude::b.adoc[]\n"),
+            ("b.adoc", "B\ninclude::a.adoc[]\n"),
+            ("main.adoc", "include::self.adoc[]\n"),
+            ("self.adoc", "include::main.adoc[]\n"),
+        ]);
+
+        let (source, warnings) = expand("include::missing.adoc[lines=1]\n", &resolver);
+        assert_eq!(source, "Unresolved directive in main.adoc - include::missing.adoc[lines=1]\n");
+        assert_eq!(warnings, ["include file not found: missing.adoc"]);
+
+        let (source, warnings) = expand("include::a.adoc[]\n", &resolver);
+        assert_eq!(source, "A\nB\nUnresolved directive in b.adoc - include::a.adoc[]\n");
+        assert_eq!(warnings, ["include cycle detected: a.adoc -> b.adoc -> a.adoc"]);
+
+        let (_, warnings) = expand("include::self.adoc[]\n", &resolver);
+        assert_eq!(warnings, ["include cycle detected: main.adoc -> self.adoc -> main.adoc"]);
+
+        let options = Options {
+            resolver: Some(&resolver),
+            path: Some("main.adoc"),
+            max_include_depth: 1,
+            ..Options::default()
+        };
+        let output = preprocess("include::a.adoc[]\n", &options);
+        assert_eq!(output.source, "A\nUnresolved directive in a.adoc - include::b.adoc[]\n");
+        assert_eq!(output.warnings[0].message, "maximum include depth of 1 exceeded");
+        assert_eq!(output.warnings[0].file.as_deref(), Some("a.adoc"));
+        assert_eq!(output.warnings[0].location.start.line, 2);
+
+        // 文档中的属性条目和覆盖属性都能修改最大深度，覆盖属性优先
+        let (source, warnings) = expand(":max-include-depth: 0
+
+include::a.adoc[]
+", &resolver);
+        assert_eq!(source, ":max-include-depth: 0
+
+Unresolved directive in main.adoc - include::a.adoc[]
+");
+        assert_eq!(warnings, ["maximum include depth of 0 exceeded"]);
+
+        let options = Options {
+            resolver: Some(&resolver),
+            path: Some("main.adoc"),
+            attributes: vec![Attribute::from_override("max-include-depth=1")],
+            ..Options::default()
+        };
+        let output = preprocess(":max-include-depth: 0
+
+include::a.adoc[]
+", &options);
+        assert_eq!(output.warnings[0].message, "maximum include depth of 1 exceeded");
+        assert_eq!(output.warnings[0].file.as_deref(), Some("a.adoc"));
+    }
+
+    #[test]
+    pub fn test_preprocess_file_resolver() {
+        let dir = std::env::temp_dir().join(format!("rusciidoc-include-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("main.adoc"), "Top\ninclude::./main.adoc[]\ninclude::part.adoc[lines=2..3]\n").unwrap();
+        fs::write(dir.join("part.adoc"), "skipped\nPart\ninclude::missing.adoc[]\n").unwrap();
+        let resolver = FileResolver { base_dir: dir.clone() };
+        let options = Options {
+            resolver: Some(&resolver),
+            path: Some("main.adoc"),
+            ..Options::default()
+        };
+        let output = preprocess(&fs::read_to_string(dir.join("main.adoc")).unwrap(), &options);
+        let main = resolver.canonical("main.adoc");
+        let part = resolver.canonical("part.adoc");
+        fs::remove_dir_all(&dir).unwrap();
+
+        // 顶层文档包含自身时立即检测到循环
+        assert_eq!(
+            output.source,
+            format!(
+                "Top\nUnresolved directive in {main} - include::./main.adoc[]\n\
+                 Part\nUnresolved directive in {part} - include::missing.adoc[]\n"
+            )
+        );
+        assert_eq!(output.warnings[0].message, format!("include cycle detected: {main} -> {main}"));
+        assert_eq!(output.warnings[0].file, None);
+
+        // 被包含文件中的警告指向该文件中原来的行
+        assert_eq!(output.warnings[1].file.as_deref(), Some(part.as_str()));
+        assert_eq!(output.warnings[1].location.start.line, 3);
+        assert_eq!(output.files.len(), 1);
+        assert_eq!(output.files[0].path, part);
+    }
+
+    #[test]
+    pub fn test_preprocess_line_map() {
+        let resolver = files(&[("part.adoc", "Part.\n\n////\nunterminated\n")]);
+        let source = "ifdef::missing[]\nSkipped.\nendif::[]\nifndef::missing[Shown.]\n\ninclude::part.adoc[]\n";
+        let options = Options {
+            resolver: Some(&resolver),
+            path: Some("main.adoc"),
+            ..Options::default()
+        };
+        let output = preprocess(source, &options);
+        assert_eq!(output.source, "Shown.\n\nPart.\n\n////\nunterminated\n");
+        let lines: Vec<_> = output.lines.iter().map(|origin| (origin.file, origin.line)).collect();
+        assert_eq!(lines, [(None, 4), (None, 5), (Some(0), 1), (Some(0), 2), (Some(0), 3), (Some(0), 4)]);
+
+        let doc = parse_document(&output.source).unwrap();
+        let diagnostic = output.remap(&doc.warnings[0]);
+        assert_eq!(diagnostic.file.as_deref(), Some("part.adoc"));
+        assert_eq!(diagnostic.location.start.line, 3);
+
+        let source = "ifdef::missing[]\nA\nendif::[]\n\n////\n";
+        let output = preprocess(source, &Options::default());
+        let doc = parse_document(&output.source).unwrap();
+        let diagnostic = output.remap(&doc.warnings[0]);
+        assert_eq!((diagnostic.file, diagnostic.location.start.line), (None, 5));
+    }
+
+    #[test]
+    pub fn test_preprocess_conditionals() {
+        let source = "\
+= Doc
+:edition-pro:
+:level: 3
+
+ifdef::edition-pro[]
+Pro only.
+ifndef::edition-free,edition-oss[]
+Not free.
+endif::[]
+endif::edition-pro[]
+ifdef::edition-pro+edition-free[Both.]
+ifndef::edition-pro[Not pro.]
+ifeval::[{level} > 2]
+Deep.
+endif::[]
+ifeval::[\"{edition}\" != \"cloud\"]
+Not cloud.
+ifdef::edition-pro[Skipped with the outer block.]
+endif::[]
+\\ifdef::x[]
+";
+        let options = Options {
+            attributes: vec![Attribute::from_override("edition=cloud")],
+            ..Options::default()
+        };
+        let output = preprocess(source, &options);
+        assert_eq!(
+            output.source,
+            "= Doc\n:edition-pro:\n:level: 3\n\nPro only.\nNot free.\nDeep.\nifdef::x[]\n"
+        );
+        assert!(output.warnings.is_empty());
+
+        let output = preprocess("ifdef::a[]\nA\nendif::b[]\nendif::[]\nendif::[]\nifeval::[1 <]\n", &options);
+        assert_eq!(output.source, "");
+        let warnings: Vec<_> = output.warnings.iter().map(|warning| warning.message.as_str()).collect();
+        assert_eq!(
+            warnings,
+            [
+                "mismatched preprocessor directive: endif::b[], expected endif::a[]",
+                "unmatched preprocessor directive: endif::[]",
+                "malformed preprocessor directive: ifeval::[1 <]",
+                "unterminated preprocessor conditional directive: ifeval::[1 <]",
+            ]
+        );
+        assert_eq!(output.warnings[1].location.start.line, 5);
+    }
+
+    #[test]
+    pub fn test_preprocess_safe_mode() {
+        let resolver = files(&[
+            ("chapters/one.adoc", "One.\ninclude::../shared/two.adoc[]\ninclude::../../secret.txt[]\n"),
+            ("shared/two.adoc", "Two.\n"),
+        ]);
+        let source = "include::chapters/one.adoc[]\ninclude::/etc/passwd[]\nifdef::safe-mode-safe[Safe.]\n";
+        let options = Options {
+            resolver: Some(&resolver),
+            safe_mode: SafeMode::Safe,
+            ..Options::default()
+        };
+        let output = preprocess(source, &options);
+        assert_eq!(
+            output.source,
+            "One.\nTwo.\nUnresolved directive in chapters/one.adoc - include::../../secret.txt[]\n\
+             Unresolved directive in <stdin> - include::/etc/passwd[]\nSafe.\n"
+        );
+        let warnings: Vec<_> = output.warnings.iter().map(|warning| warning.message.as_str()).collect();
+        assert_eq!(
+            warnings,
+            [
+                "include file is outside the base directory: ../../secret.txt",
+                "include file is outside the base directory: /etc/passwd",
+            ]
+        );
+        assert_eq!(output.warnings[0].file.as_deref(), Some("chapters/one.adoc"));
+
+        let options = Options {
+            resolver: Some(&resolver),
+            safe_mode: SafeMode::Secure,
+            ..Options::default()
+        };
+        let output = preprocess("include::chapters/one.adoc[]\n", &options);
+        assert_eq!(output.source, "Unresolved directive in <stdin> - include::chapters/one.adoc[]\n");
+        assert_eq!(output.warnings[0].message, "include directive is disabled in secure mode");
+    }
+
+    #[test]
+    pub fn test_preprocess_safe_mode_absolute_include() {
+        let dir = std::env::temp_dir().join(format!("rusciidoc-safe-{}", std::process::id()));
+        fs::create_dir_all(dir.join("docs")).unwrap();
+        fs::write(dir.join("docs/chapter.adoc"), "Chapter.\n").unwrap();
+        fs::write(dir.join("secret.txt"), "Secret.\n").unwrap();
+        let resolver = FileResolver { base_dir: dir.join("docs") };
+        let docdir = fs::canonicalize(dir.join("docs")).unwrap().display().to_string();
+        let docdir = format!("docdir={docdir}");
+        let options = Options {
+            resolver: Some(&resolver),
+            path: Some("main.adoc"),
+            attributes: vec![Attribute::from_override(&docdir)],
+            safe_mode: SafeMode::Safe,
+            ..Options::default()
+        };
+        let output = preprocess("include::{docdir}/chapter.adoc[]\ninclude::{docdir}/../secret.txt[]\n", &options);
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(output.source.starts_with("Chapter.\nUnresolved directive in "));
+        let warnings: Vec<_> = output.warnings.iter().map(|warning| warning.message.as_str()).collect();
+        let outside = format!("include file is outside the base directory: {}/../secret.txt", &docdir[7..]);
+        assert_eq!(warnings, [outside.as_str()]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    pub fn test_preprocess_safe_mode_symlink() {
+        use std::os::unix::fs::symlink;
+
+        let dir = std::env::temp_dir().join(format!("rusciidoc-symlink-{}", std::process::id()));
+        fs::create_dir_all(dir.join("docs/real")).unwrap();
+        fs::create_dir_all(dir.join("outside")).unwrap();
+        fs::write(dir.join("outside/secret.adoc"), "Secret.\n").unwrap();
+        fs::write(dir.join("docs/real/part.adoc"), "Part.\ninclude::../shared.adoc[]\n").unwrap();
+        fs::write(dir.join("docs/shared.adoc"), "Shared.\n").unwrap();
+        symlink(dir.join("outside"), dir.join("docs/escape")).unwrap();
+        symlink(dir.join("docs/real"), dir.join("docs/alias")).unwrap();
+        let resolver = FileResolver { base_dir: dir.join("docs") };
+        let options = Options {
+            resolver: Some(&resolver),
+            path: Some("main.adoc"),
+            safe_mode: SafeMode::Safe,
+            ..Options::default()
+        };
+        let output = preprocess("include::escape/secret.adoc[]\ninclude::alias/part.adoc[]\n", &options);
+        let unsafe_output = preprocess("include::escape/secret.adoc[]\n", &Options { safe_mode: SafeMode::Unsafe, ..options });
+        fs::remove_dir_all(&dir).unwrap();
+
+        // 指向目录之外的符号链接不能绕过检查，目录之内的符号链接中的相对路径按链接的目标解析
+        assert!(output.source.starts_with("Unresolved directive in "));
+        assert!(output.source.ends_with(" - include::escape/secret.adoc[]\nPart.\nShared.\n"));
+        let warnings: Vec<_> = output.warnings.iter().map(|warning| warning.message.as_str()).collect();
+        assert_eq!(warnings, ["include file is outside the base directory: escape/secret.adoc"]);
+        assert_eq!(unsafe_output.source, "Secret.\n");
+    }
+}
@@ -0,0 +1,96 @@
+//! 处理不可信文档时使用的安全模式
+
+use std::fmt;
+use std::str::FromStr;
+
+use crate::ast::{Attribute, Location};
+
+/// 安全模式，和 Asciidoctor 的级别一致，后面的模式包含前面模式的全部限制
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum SafeMode {
+    /// 没有限制
+    #[default]
+    Unsafe,
+    /// `include::` 只能包含顶层文档所在目录之内的文件
+    Safe,
+    /// 隐藏 `docdir` 和 `docfile`，直通块按原样文本转义输出
+    Server,
+    /// 禁用 `include::`
+    Secure,
+}
+
+impl FromStr for SafeMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "unsafe" => Ok(SafeMode::Unsafe),
+            "safe" => Ok(SafeMode::Safe),
+            "server" => Ok(SafeMode::Server),
+            "secure" => Ok(SafeMode::Secure),
+            _ => Err(format!("unknown safe mode: {s}")),
+        }
+    }
+}
+
+impl fmt::Display for SafeMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl SafeMode {
+    pub fn name(self) -> &'static str {
+        match self {
+            SafeMode::Unsafe => "unsafe",
+            SafeMode::Safe => "safe",
+            SafeMode::Server => "server",
+            SafeMode::Secure => "secure",
+        }
+    }
+
+    /// 由处理器设置且文档不能修改的属性，文档可以用 `ifdef::safe-mode-secure[]` 判断所处的模式
+    pub fn attributes(self) -> Vec<Attribute<'static>> {
+        let (flag, level) = match self {
+            SafeMode::Unsafe => ("safe-mode-unsafe", "0"),
+            SafeMode::Safe => ("safe-mode-safe", "1"),
+            SafeMode::Server => ("safe-mode-server", "10"),
+            SafeMode::Secure => ("safe-mode-secure", "20"),
+        };
+        let mut values = vec![("safe-mode-name", self.name()), ("safe-mode-level", level), (flag, "")];
+        if self >= SafeMode::Server {
+            values.extend([("docdir", ""), ("docfile", "")]);
+        }
+        values
+            .into_iter()
+            .map(|(name, value)| Attribute {
+                unset: false,
+                name,
+                value: Some(value),
+                location: Location::default(),
+            })
+            .collect()
+    }
+
+    /// 属性是否由该模式锁定
+    pub fn locks(self, name: &str) -> bool {
+        self.attributes().iter().any(|attr| attr.name.eq_ignore_ascii_case(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::safe_mode::SafeMode;
+
+    #[test]
+    pub fn test_safe_mode() {
+        assert_eq!("server".parse::<SafeMode>(), Ok(SafeMode::Server));
+        assert!("paranoid".parse::<SafeMode>().is_err());
+        assert!(SafeMode::Secure > SafeMode::Safe);
+
+        let names: Vec<_> = SafeMode::Safe.attributes().iter().map(|attr| attr.name).collect();
+        assert_eq!(names, ["safe-mode-name", "safe-mode-level", "safe-mode-safe"]);
+        assert!(!SafeMode::Safe.locks("docdir"));
+        assert!(SafeMode::Secure.locks("DocDir"));
+    }
+}